@@ -0,0 +1,51 @@
+const passwordResetForm = document.getElementById("password-reset-form");
+const passwordResetButton = document.getElementById("password-reset-form-submit");
+const passwordResetErrAlert = document.getElementById("password-reset-err-alert");
+const passwordResetDoneAlert = document.getElementById("password-reset-done-alert");
+
+// The emailed link carries the token as ?token=...
+const token = new URLSearchParams(window.location.search).get("token");
+
+function showError(error_msg) {
+    passwordResetErrAlert.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+    passwordResetErrAlert.style.display = "block";
+}
+
+if (!token) {
+    showError("This link is incomplete. Request a new password reset email.");
+    passwordResetButton.disabled = true;
+}
+
+passwordResetButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const newPassword = passwordResetForm.password.value;
+    if (newPassword !== passwordResetForm.confirm.value) {
+        showError("The passwords do not match.");
+        return;
+    }
+
+    // Relative, so the page works under the path prefix the service is served from
+    fetch('password-reset/confirm', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token, newPassword }),
+    }).then(response => {
+        if (response.ok) {
+            passwordResetForm.style.display = "none";
+            passwordResetErrAlert.style.display = "none";
+            passwordResetDoneAlert.style.display = "block";
+        } else {
+            response.json().then(data => {
+                let error_msg = data.error;
+                if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+                    showError(error_msg);
+                } else {
+                    passwordResetErrAlert.style.display = "none";
+                }
+            });
+        }
+    });
+});
@@ -0,0 +1,5 @@
+ALTER TABLE users
+    DROP COLUMN IF EXISTS totp_last_step,
+    DROP COLUMN IF EXISTS pending_totp_secret,
+    DROP COLUMN IF EXISTS totp_secret,
+    DROP COLUMN IF EXISTS two_fa_method;
\ No newline at end of file
@@ -0,0 +1,8 @@
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS two_fa_method TEXT NOT NULL DEFAULT 'email'
+        CHECK (two_fa_method IN ('email', 'totp')),
+    -- AES-256-GCM encrypted; the pending secret waits for the first code before replacing the active one
+    ADD COLUMN IF NOT EXISTS totp_secret BYTEA,
+    ADD COLUMN IF NOT EXISTS pending_totp_secret BYTEA,
+    -- Last accepted time step, so a code can't be replayed within its validity window
+    ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS passkey_credentials(
+   credential_id BYTEA NOT NULL PRIMARY KEY,
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE,
+   -- COSE algorithm identifier and the raw public key for it
+   algorithm INTEGER NOT NULL,
+   public_key BYTEA NOT NULL,
+   sign_count BIGINT NOT NULL DEFAULT 0,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   last_used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS passkey_credentials_email_idx ON passkey_credentials(email);
@@ -0,0 +1,3 @@
+-- Disabled accounts can't log in; a forced reset refuses password logins until the password is replaced
+ALTER TABLE users ADD COLUMN IF NOT EXISTS disabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS password_reset_required BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Set while an account waits out its deletion grace period; the account is purged once it passes
+ALTER TABLE users ADD COLUMN IF NOT EXISTS delete_after TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS users_delete_after_idx ON users (delete_after) WHERE delete_after IS NOT NULL;
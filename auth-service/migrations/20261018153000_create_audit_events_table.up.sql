@@ -0,0 +1,11 @@
+-- What was done in a user's name, e.g. logins and password changes, for them to review and export
+CREATE TABLE IF NOT EXISTS audit_events(
+   id BIGSERIAL PRIMARY KEY,
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE,
+   event TEXT NOT NULL,
+   ip TEXT,
+   user_agent TEXT,
+   occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_email_idx ON audit_events(email);
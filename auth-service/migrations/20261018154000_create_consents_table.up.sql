@@ -0,0 +1,8 @@
+-- The user's latest answer per purpose, e.g. whether they want the newsletter
+CREATE TABLE IF NOT EXISTS consents(
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE,
+   purpose TEXT NOT NULL,
+   granted BOOLEAN NOT NULL,
+   updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   PRIMARY KEY (email, purpose)
+);
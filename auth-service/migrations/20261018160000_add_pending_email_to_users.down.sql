@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS users_pending_email_idx;
+ALTER TABLE users DROP COLUMN IF EXISTS pending_email;
@@ -0,0 +1,4 @@
+-- Address the user asked to switch to, until the confirmation link sent there is followed
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pending_email TEXT;
+-- One pending change per address, so the link confirms exactly one account
+CREATE UNIQUE INDEX IF NOT EXISTS users_pending_email_idx ON users (pending_email) WHERE pending_email IS NOT NULL;
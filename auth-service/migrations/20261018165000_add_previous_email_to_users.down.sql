@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS users_previous_email_idx;
+ALTER TABLE users DROP COLUMN IF EXISTS email_changed_at;
+ALTER TABLE users DROP COLUMN IF EXISTS previous_email;
@@ -0,0 +1,4 @@
+-- The address an account left, kept while its owner can still undo the change
+ALTER TABLE users ADD COLUMN IF NOT EXISTS previous_email TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_changed_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS users_previous_email_idx ON users (previous_email) WHERE previous_email IS NOT NULL;
@@ -0,0 +1,44 @@
+ALTER TABLE recovery_codes ADD COLUMN email TEXT;
+UPDATE recovery_codes SET email = users.email FROM users WHERE users.id = recovery_codes.user_id;
+ALTER TABLE recovery_codes DROP COLUMN user_id;
+
+ALTER TABLE passkey_credentials ADD COLUMN email TEXT;
+UPDATE passkey_credentials SET email = users.email FROM users WHERE users.id = passkey_credentials.user_id;
+ALTER TABLE passkey_credentials DROP COLUMN user_id;
+
+ALTER TABLE user_roles ADD COLUMN email TEXT;
+UPDATE user_roles SET email = users.email FROM users WHERE users.id = user_roles.user_id;
+ALTER TABLE user_roles DROP COLUMN user_id;
+
+ALTER TABLE audit_events ADD COLUMN email TEXT;
+UPDATE audit_events SET email = users.email FROM users WHERE users.id = audit_events.user_id;
+ALTER TABLE audit_events DROP COLUMN user_id;
+
+ALTER TABLE consents ADD COLUMN email TEXT;
+UPDATE consents SET email = users.email FROM users WHERE users.id = consents.user_id;
+ALTER TABLE consents DROP COLUMN user_id;
+
+ALTER TABLE users DROP CONSTRAINT users_pkey;
+ALTER TABLE users DROP CONSTRAINT users_email_key;
+ALTER TABLE users ADD PRIMARY KEY (email);
+ALTER TABLE users DROP COLUMN id;
+
+ALTER TABLE recovery_codes ALTER COLUMN email SET NOT NULL;
+ALTER TABLE recovery_codes ADD FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+CREATE INDEX IF NOT EXISTS recovery_codes_email_idx ON recovery_codes(email);
+
+ALTER TABLE passkey_credentials ALTER COLUMN email SET NOT NULL;
+ALTER TABLE passkey_credentials ADD FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+CREATE INDEX IF NOT EXISTS passkey_credentials_email_idx ON passkey_credentials(email);
+
+ALTER TABLE user_roles ALTER COLUMN email SET NOT NULL;
+ALTER TABLE user_roles ADD FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE user_roles ADD PRIMARY KEY (email, role);
+
+ALTER TABLE audit_events ALTER COLUMN email SET NOT NULL;
+ALTER TABLE audit_events ADD FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+CREATE INDEX IF NOT EXISTS audit_events_email_idx ON audit_events(email);
+
+ALTER TABLE consents ALTER COLUMN email SET NOT NULL;
+ALTER TABLE consents ADD FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE consents ADD PRIMARY KEY (email, purpose);
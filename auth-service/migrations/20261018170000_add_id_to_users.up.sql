@@ -0,0 +1,49 @@
+-- Users are identified by a stable id from now on; the email stays unique but may change.
+-- The volatile default gives every existing user their own id.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS id UUID NOT NULL DEFAULT gen_random_uuid();
+
+-- Point the tables hanging off users at the id instead of the email
+ALTER TABLE recovery_codes ADD COLUMN user_id UUID;
+UPDATE recovery_codes SET user_id = users.id FROM users WHERE users.email = recovery_codes.email;
+ALTER TABLE recovery_codes ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE recovery_codes DROP COLUMN email;
+
+ALTER TABLE passkey_credentials ADD COLUMN user_id UUID;
+UPDATE passkey_credentials SET user_id = users.id FROM users WHERE users.email = passkey_credentials.email;
+ALTER TABLE passkey_credentials ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE passkey_credentials DROP COLUMN email;
+
+ALTER TABLE user_roles ADD COLUMN user_id UUID;
+UPDATE user_roles SET user_id = users.id FROM users WHERE users.email = user_roles.email;
+ALTER TABLE user_roles ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE user_roles DROP COLUMN email;
+
+ALTER TABLE audit_events ADD COLUMN user_id UUID;
+UPDATE audit_events SET user_id = users.id FROM users WHERE users.email = audit_events.email;
+ALTER TABLE audit_events ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE audit_events DROP COLUMN email;
+
+ALTER TABLE consents ADD COLUMN user_id UUID;
+UPDATE consents SET user_id = users.id FROM users WHERE users.email = consents.email;
+ALTER TABLE consents ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE consents DROP COLUMN email;
+
+-- Nothing references the email any more, so the key can move
+ALTER TABLE users DROP CONSTRAINT users_pkey;
+ALTER TABLE users ADD PRIMARY KEY (id);
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+
+ALTER TABLE recovery_codes ADD FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS recovery_codes_user_id_idx ON recovery_codes(user_id);
+
+ALTER TABLE passkey_credentials ADD FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS passkey_credentials_user_id_idx ON passkey_credentials(user_id);
+
+ALTER TABLE user_roles ADD FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE user_roles ADD PRIMARY KEY (user_id, role);
+
+ALTER TABLE audit_events ADD FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS audit_events_user_id_idx ON audit_events(user_id);
+
+ALTER TABLE consents ADD FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE consents ADD PRIMARY KEY (user_id, purpose);
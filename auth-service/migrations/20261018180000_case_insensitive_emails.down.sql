@@ -0,0 +1,9 @@
+-- Addresses stay lowercase; their original spelling is gone
+DROP INDEX IF EXISTS users_previous_email_idx;
+CREATE INDEX IF NOT EXISTS users_previous_email_idx ON users (previous_email) WHERE previous_email IS NOT NULL;
+
+DROP INDEX IF EXISTS users_pending_email_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS users_pending_email_idx ON users (pending_email) WHERE pending_email IS NOT NULL;
+
+DROP INDEX IF EXISTS users_email_lower_key;
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
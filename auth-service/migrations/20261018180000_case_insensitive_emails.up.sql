@@ -0,0 +1,59 @@
+-- Emails are unique regardless of case from now on. Accounts that only differ in case have to be
+-- merged or renamed by hand first, so refuse to run while any exist.
+-- Stored addresses must also match Email::parse exactly: lowercase throughout, domains in punycode.
+-- SQL can't produce punycode, nor lowercase beyond ASCII the way Email::parse does, so an account
+-- stored under an international domain couldn't be found, or might duplicate one stored under the
+-- punycode spelling. Refuse to run until non-ASCII addresses have been normalized by hand too.
+DO $$
+DECLARE
+    collisions TEXT;
+    international TEXT;
+BEGIN
+    SELECT string_agg(emails, '; ') INTO collisions
+    FROM (
+        SELECT string_agg(email, ', ' ORDER BY email) AS emails
+        FROM users
+        GROUP BY lower(btrim(email))
+        HAVING COUNT(*) > 1
+    ) AS colliding;
+    IF collisions IS NOT NULL THEN
+        RAISE EXCEPTION 'Emails that differ only in case: %', collisions;
+    END IF;
+
+    SELECT string_agg(emails, '; ') INTO collisions
+    FROM (
+        SELECT string_agg(pending_email, ', ' ORDER BY pending_email) AS emails
+        FROM users
+        WHERE pending_email IS NOT NULL
+        GROUP BY lower(btrim(pending_email))
+        HAVING COUNT(*) > 1
+    ) AS colliding;
+    IF collisions IS NOT NULL THEN
+        RAISE EXCEPTION 'Pending emails that differ only in case: %', collisions;
+    END IF;
+
+    SELECT string_agg(address, ', ' ORDER BY address) INTO international
+    FROM (
+        SELECT email AS address FROM users
+        UNION ALL SELECT pending_email FROM users WHERE pending_email IS NOT NULL
+        UNION ALL SELECT previous_email FROM users WHERE previous_email IS NOT NULL
+    ) AS addresses
+    WHERE address ~ '[^\x01-\x7F]';
+    IF international IS NOT NULL THEN
+        RAISE EXCEPTION 'Non-ASCII emails, lowercase them and convert domains to punycode: %', international;
+    END IF;
+END $$;
+
+-- Only ASCII is left, so lower() matches Email::parse: trimmed and lowercase throughout
+UPDATE users SET email = lower(btrim(email)) WHERE email <> lower(btrim(email));
+UPDATE users SET pending_email = lower(btrim(pending_email)) WHERE pending_email <> lower(btrim(pending_email));
+UPDATE users SET previous_email = lower(btrim(previous_email)) WHERE previous_email <> lower(btrim(previous_email));
+
+ALTER TABLE users DROP CONSTRAINT users_email_key;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_lower_key ON users (lower(email));
+
+DROP INDEX IF EXISTS users_pending_email_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS users_pending_email_idx ON users (lower(pending_email)) WHERE pending_email IS NOT NULL;
+
+DROP INDEX IF EXISTS users_previous_email_idx;
+CREATE INDEX IF NOT EXISTS users_previous_email_idx ON users (lower(previous_email)) WHERE previous_email IS NOT NULL;
@@ -1,4 +1,16 @@
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::{
+    domain::{
+        AdminKey, ApiClients, AuditEventStore, BannedTokenStore, ConsentStore, EmailClient,
+        MagicLinkStore, OneTimeTokenStore, PasskeyStore, PersonalDataExporter, RateLimitStore,
+        RateLimits, RefreshTokenStore, SessionStore, TrustedProxies, TwoFACodeStore,
+        UnverifiedLoginPolicy, UserStore,
+    },
+    utils::{
+        SharedBannedTokenStore, ADMIN_KEY, API_CLIENTS, JWT_AUDIENCES, TRUSTED_PROXIES,
+        UNVERIFIED_LOGIN_POLICY,
+    },
+};
+use axum::extract::FromRef;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -8,22 +20,131 @@ pub struct AppState {
     pub user_store: Arc<RwLock<dyn UserStore + Send + Sync>>,
     pub banned_token_store: Arc<RwLock<dyn BannedTokenStore + Send + Sync>>,
     pub two_fa_code_store: Arc<RwLock<dyn TwoFACodeStore + Send + Sync>>,
+    pub refresh_token_store: Arc<RwLock<dyn RefreshTokenStore + Send + Sync>>,
+    pub one_time_token_store: Arc<RwLock<dyn OneTimeTokenStore + Send + Sync>>,
+    pub passkey_store: Arc<RwLock<dyn PasskeyStore + Send + Sync>>,
+    pub magic_link_store: Arc<RwLock<dyn MagicLinkStore + Send + Sync>>,
+    pub session_store: Arc<RwLock<dyn SessionStore + Send + Sync>>,
+    pub rate_limit_store: Arc<RwLock<dyn RateLimitStore + Send + Sync>>,
+    pub audit_event_store: Arc<RwLock<dyn AuditEventStore + Send + Sync>>,
+    pub consent_store: Arc<RwLock<dyn ConsentStore + Send + Sync>>,
     pub email_client: Arc<dyn EmailClient + Send + Sync>,
+    pub unverified_login_policy: UnverifiedLoginPolicy,
+    /// Backend services allowed to call server-to-server endpoints
+    pub api_clients: ApiClients,
+    /// Downstream services users can get tokens minted for
+    pub token_audiences: Vec<String>,
+    /// Opens the admin API to scripts; without it only admin users get in
+    pub admin_key: Option<AdminKey>,
+    /// Attempts allowed on the login and 2FA routes
+    pub rate_limits: RateLimits,
+    /// Proxies allowed to tell the client address in `X-Real-IP`
+    pub trusted_proxies: TrustedProxies,
 }
 
 impl AppState {
     /// Creates a new `AppState` with the given stores.
+    /// The unverified login policy, API clients, token audiences, admin key and trusted proxies
+    /// come from the environment
+    /// and rate limits are the defaults; override the fields to change them.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_store: Arc<RwLock<dyn UserStore + Send + Sync>>,
         banned_token_store: Arc<RwLock<dyn BannedTokenStore + Send + Sync>>,
         two_fa_code_store: Arc<RwLock<dyn TwoFACodeStore + Send + Sync>>,
+        refresh_token_store: Arc<RwLock<dyn RefreshTokenStore + Send + Sync>>,
+        one_time_token_store: Arc<RwLock<dyn OneTimeTokenStore + Send + Sync>>,
+        passkey_store: Arc<RwLock<dyn PasskeyStore + Send + Sync>>,
+        magic_link_store: Arc<RwLock<dyn MagicLinkStore + Send + Sync>>,
+        session_store: Arc<RwLock<dyn SessionStore + Send + Sync>>,
+        rate_limit_store: Arc<RwLock<dyn RateLimitStore + Send + Sync>>,
+        audit_event_store: Arc<RwLock<dyn AuditEventStore + Send + Sync>>,
+        consent_store: Arc<RwLock<dyn ConsentStore + Send + Sync>>,
         email_client: Arc<dyn EmailClient + Send + Sync>,
     ) -> Self {
         Self {
             user_store,
             banned_token_store,
             two_fa_code_store,
+            refresh_token_store,
+            one_time_token_store,
+            passkey_store,
+            magic_link_store,
+            session_store,
+            rate_limit_store,
+            audit_event_store,
+            consent_store,
             email_client,
+            unverified_login_policy: *UNVERIFIED_LOGIN_POLICY,
+            api_clients: API_CLIENTS.clone(),
+            token_audiences: JWT_AUDIENCES.clone(),
+            admin_key: ADMIN_KEY.clone(),
+            rate_limits: RateLimits::default(),
+            trusted_proxies: TRUSTED_PROXIES.clone(),
         }
     }
+
+    /// Every store, as seen by the account export.
+    pub fn personal_data_exporters(&self) -> Vec<SharedPersonalDataExporter> {
+        // No `..`: a new field doesn't compile until it is listed here or set aside below
+        let Self {
+            user_store,
+            banned_token_store,
+            two_fa_code_store,
+            refresh_token_store,
+            one_time_token_store,
+            passkey_store,
+            magic_link_store,
+            session_store,
+            rate_limit_store,
+            audit_event_store,
+            consent_store,
+            email_client: _,
+            unverified_login_policy: _,
+            api_clients: _,
+            token_audiences: _,
+            admin_key: _,
+            rate_limits: _,
+            trusted_proxies: _,
+        } = self;
+        vec![
+            user_store.clone(),
+            session_store.clone(),
+            audit_event_store.clone(),
+            consent_store.clone(),
+            passkey_store.clone(),
+            refresh_token_store.clone(),
+            two_fa_code_store.clone(),
+            one_time_token_store.clone(),
+            magic_link_store.clone(),
+            banned_token_store.clone(),
+            rate_limit_store.clone(),
+        ]
+    }
+}
+
+pub type SharedPersonalDataExporter = Arc<RwLock<dyn PersonalDataExporter + Send + Sync>>;
+
+impl FromRef<AppState> for SharedBannedTokenStore {
+    fn from_ref(state: &AppState) -> Self {
+        state.banned_token_store.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<AdminKey> {
+    fn from_ref(state: &AppState) -> Self {
+        state.admin_key.clone()
+    }
+}
+
+impl FromRef<AppState> for ApiClients {
+    fn from_ref(state: &AppState) -> Self {
+        state.api_clients.clone()
+    }
+}
+
+impl FromRef<AppState> for TrustedProxies {
+    fn from_ref(state: &AppState) -> Self {
+        state.trusted_proxies.clone()
+    }
 }
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod app_state;
 
 pub use app_state::*;
@@ -0,0 +1,213 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::{eyre, Report};
+use data_encoding::HEXLOWER_PERMISSIVE;
+use ring::digest;
+use secrecy::{ExposeSecret, Secret};
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+
+use super::AuthAPIError;
+
+/// A backend service allowed to call server-to-server endpoints such as `/introspect`.
+/// Only the SHA-256 digest of its secret is configured, so the secret never sits in the environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiClient {
+    pub id: String,
+    secret_digest: Vec<u8>,
+}
+
+impl ApiClient {
+    pub fn new(id: String, secret: &Secret<String>) -> Self {
+        Self {
+            id,
+            secret_digest: digest_secret(secret),
+        }
+    }
+
+    /// Client whose secret has the given hex-encoded SHA-256 digest,
+    /// e.g. the output of `printf %s "$SECRET" | sha256sum`.
+    pub fn from_hex_digest(id: String, hex_digest: &str) -> Result<Self, Report> {
+        let secret_digest = decode_hex_digest(hex_digest)
+            .map_err(|e| eyre!("secret digest for API client {}: {}", id, e))?;
+        Ok(Self { id, secret_digest })
+    }
+
+    fn verify_secret(&self, secret: &Secret<String>) -> bool {
+        digests_match(&digest_secret(secret), &self.secret_digest)
+    }
+}
+
+/// The API clients known to the service. Empty unless configured.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiClients(Vec<ApiClient>);
+
+impl ApiClients {
+    pub fn new(clients: Vec<ApiClient>) -> Self {
+        Self(clients)
+    }
+
+    /// Look up the client and check its secret.
+    pub fn authenticate(&self, id: &str, secret: &Secret<String>) -> Option<&ApiClient> {
+        self.0
+            .iter()
+            .find(|client| client.id == id)
+            .filter(|client| client.verify_secret(secret))
+    }
+}
+
+/// Parses `id:sha256hex` pairs separated by commas.
+impl FromStr for ApiClients {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (id, hex_digest) = entry
+                    .split_once(':')
+                    .ok_or(eyre!("API client entry must be id:digest"))?;
+                ApiClient::from_hex_digest(id.trim().to_owned(), hex_digest.trim())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+/// Key that opens the admin API to scripts, without an admin user's token.
+/// Configured as a SHA-256 digest, like API client secrets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminKey {
+    digest: Vec<u8>,
+}
+
+impl AdminKey {
+    pub fn new(key: &Secret<String>) -> Self {
+        Self {
+            digest: digest_secret(key),
+        }
+    }
+
+    pub fn from_hex_digest(hex_digest: &str) -> Result<Self, Report> {
+        let digest = decode_hex_digest(hex_digest).map_err(|e| eyre!("admin key digest: {}", e))?;
+        Ok(Self { digest })
+    }
+
+    pub fn verify(&self, key: &Secret<String>) -> bool {
+        digests_match(&digest_secret(key), &self.digest)
+    }
+}
+
+/// An API client that authenticated the request with HTTP Basic credentials (RFC 6749 section 2.3.1).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticatedClient(pub ApiClient);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthenticatedClient
+where
+    ApiClients: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let (id, secret) = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_basic_credentials)
+            .ok_or(AuthAPIError::InvalidClient)?;
+
+        ApiClients::from_ref(state)
+            .authenticate(&id, &secret)
+            .cloned()
+            .map(Self)
+            .ok_or(AuthAPIError::InvalidClient)
+    }
+}
+
+fn parse_basic_credentials(header: &str) -> Option<(String, Secret<String>)> {
+    let (scheme, encoded) = header.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+    Some((id.to_owned(), Secret::new(secret.to_owned())))
+}
+
+fn decode_hex_digest(hex_digest: &str) -> Result<Vec<u8>, Report> {
+    let digest = HEXLOWER_PERMISSIVE
+        .decode(hex_digest.as_bytes())
+        .map_err(|e| eyre!("invalid hex: {}", e))?;
+    if digest.len() != digest::SHA256_OUTPUT_LEN {
+        return Err(eyre!("not a SHA-256 digest"));
+    }
+    Ok(digest)
+}
+
+fn digest_secret(secret: &Secret<String>) -> Vec<u8> {
+    digest::digest(&digest::SHA256, secret.expose_secret().as_bytes())
+        .as_ref()
+        .to_vec()
+}
+
+// Constant time, so response timing can't be used to work out a digest byte by byte
+fn digests_match(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SHA-256 of "secret"
+    const SECRET_DIGEST: &str = "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b";
+
+    #[test]
+    fn test_parse_clients_and_authenticate() {
+        let clients: ApiClients = format!(" api:{} ,", SECRET_DIGEST).parse().unwrap();
+
+        let secret = Secret::new("secret".to_owned());
+        assert_eq!(
+            clients.authenticate("api", &secret).map(|c| c.id.as_str()),
+            Some("api")
+        );
+        assert!(clients
+            .authenticate("api", &Secret::new("wrong".to_owned()))
+            .is_none());
+        assert!(clients.authenticate("other", &secret).is_none());
+    }
+
+    #[test]
+    fn test_parse_clients_rejects_malformed_entries() {
+        assert!("api".parse::<ApiClients>().is_err());
+        assert!("api:not-hex".parse::<ApiClients>().is_err());
+        assert!("api:abcd".parse::<ApiClients>().is_err());
+        assert_eq!("".parse::<ApiClients>().unwrap(), ApiClients::default());
+    }
+
+    #[test]
+    fn test_admin_key_from_hex_digest() {
+        let key = AdminKey::from_hex_digest(SECRET_DIGEST).unwrap();
+        assert!(key.verify(&Secret::new("secret".to_owned())));
+        assert!(!key.verify(&Secret::new("wrong".to_owned())));
+        assert!(AdminKey::from_hex_digest("abcd").is_err());
+    }
+
+    #[test]
+    fn test_parse_basic_credentials() {
+        let header = format!("Basic {}", STANDARD.encode("api:s3cret:with-colon"));
+        let (id, secret) = parse_basic_credentials(&header).unwrap();
+        assert_eq!(id, "api");
+        assert_eq!(secret.expose_secret(), "s3cret:with-colon");
+
+        assert!(parse_basic_credentials("Bearer abc").is_none());
+        assert!(parse_basic_credentials("Basic !!!").is_none());
+    }
+}
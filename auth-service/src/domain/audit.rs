@@ -0,0 +1,31 @@
+/// Something done in a user's name that they may want to review, kept with where it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEventKind {
+    Signup,
+    Login,
+    Logout,
+    /// Every session ended at once by the user
+    LogoutAll,
+    PasswordChanged,
+    /// Password set through an emailed reset link
+    PasswordReset,
+    DeletionRequested,
+    DeletionCancelled,
+    ConsentChanged,
+}
+
+impl AuditEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEventKind::Signup => "signup",
+            AuditEventKind::Login => "login",
+            AuditEventKind::Logout => "logout",
+            AuditEventKind::LogoutAll => "logout_all",
+            AuditEventKind::PasswordChanged => "password_changed",
+            AuditEventKind::PasswordReset => "password_reset",
+            AuditEventKind::DeletionRequested => "deletion_requested",
+            AuditEventKind::DeletionCancelled => "deletion_cancelled",
+            AuditEventKind::ConsentChanged => "consent_changed",
+        }
+    }
+}
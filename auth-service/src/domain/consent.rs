@@ -0,0 +1,52 @@
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+/// What a user can agree to, e.g. `newsletter`. Same shape as a role name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsentPurpose(String);
+
+impl ConsentPurpose {
+    pub fn parse(s: &str) -> Result<Self> {
+        let valid = !s.is_empty()
+            && s.len() <= 64
+            && s.bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+        if !valid {
+            return Err(eyre!("Invalid consent purpose"));
+        }
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl AsRef<str> for ConsentPurpose {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The user's latest answer for one purpose.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Consent {
+    pub purpose: String,
+    pub granted: bool,
+    /// Unix timestamp of the answer
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_consent_purpose() {
+        assert_eq!(
+            ConsentPurpose::parse("newsletter").unwrap().as_ref(),
+            "newsletter"
+        );
+        assert!(ConsentPurpose::parse("product_analytics-2").is_ok());
+        assert!(ConsentPurpose::parse("").is_err());
+        assert!(ConsentPurpose::parse("Newsletter").is_err());
+        assert!(ConsentPurpose::parse(&"a".repeat(65)).is_err());
+    }
+}
@@ -1,20 +1,149 @@
-use crate::domain::{Email, Password, User};
+use crate::domain::{
+    AuditEventKind, ClientInfo, Consent, ConsentPurpose, Email, PasskeyCredential, Password,
+    PersonalDataExporter, RateLimit, RecoveryCode, Role, RoleDefinition, Session,
+    StoredRecoveryCode, TotpSecret, User, UserId, UserRoles, ACCOUNT_DELETION_GRACE_SECONDS,
+    EMAIL_CHANGE_REVERT_SECONDS,
+};
 use color_eyre::eyre::{eyre, Context, Report, Result};
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use serde::Serialize;
+use std::time::Duration;
 use thiserror::Error;
 
 #[async_trait::async_trait]
-pub trait UserStore: Send + Sync {
+pub trait UserStore: PersonalDataExporter {
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
-    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError>;
-    async fn validate_user(&self, email: &Email, password: &Password)
-        -> Result<(), UserStoreError>;
+    async fn get_user(&self, user_id: &UserId) -> Result<User, UserStoreError>;
+    async fn get_user_by_email(&self, email: &Email) -> Result<User, UserStoreError>;
+    /// Check `password` as entered against the user's hash, without applying the password policy
+    /// to it: a password set under an older policy must still be accepted.
+    async fn validate_user(
+        &self,
+        user_id: &UserId,
+        password: &Secret<String>,
+    ) -> Result<(), UserStoreError>;
     async fn authenticate_user(
         &self,
-        email: &str,
+        email: &Email,
         password: &Secret<String>,
     ) -> Result<User, UserStoreError>;
+    async fn update_password(
+        &mut self,
+        user_id: &UserId,
+        password: Password,
+    ) -> Result<(), UserStoreError>;
+    async fn mark_email_verified(&mut self, user_id: &UserId) -> Result<(), UserStoreError>;
+    /// Store a TOTP secret that only takes effect once `activate_totp` is called.
+    async fn set_pending_totp_secret(
+        &mut self,
+        user_id: &UserId,
+        secret: &TotpSecret,
+    ) -> Result<(), UserStoreError>;
+    async fn get_pending_totp_secret(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Option<TotpSecret>, UserStoreError>;
+    async fn get_totp_secret(&self, user_id: &UserId)
+        -> Result<Option<TotpSecret>, UserStoreError>;
+    /// Promote the pending secret and switch the user to TOTP 2FA.
+    /// `step` is the time step of the code that confirmed enrollment.
+    async fn activate_totp(&mut self, user_id: &UserId, step: u64) -> Result<(), UserStoreError>;
+    /// Record `step` as used; returns false if it (or a later step) was already accepted.
+    async fn record_totp_step(
+        &mut self,
+        user_id: &UserId,
+        step: u64,
+    ) -> Result<bool, UserStoreError>;
+    /// Replace all recovery codes of the user, used or not, with `codes`.
+    async fn replace_recovery_codes(
+        &mut self,
+        user_id: &UserId,
+        codes: &[RecoveryCode],
+    ) -> Result<(), UserStoreError>;
+    /// The user's unused recovery codes, hashed.
+    async fn get_recovery_codes(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<StoredRecoveryCode>, UserStoreError>;
+    /// Mark the code with `code_id` as used; returns false if it was already used.
+    async fn use_recovery_code(
+        &mut self,
+        user_id: &UserId,
+        code_id: i64,
+    ) -> Result<bool, UserStoreError>;
+    async fn count_recovery_codes(&self, user_id: &UserId) -> Result<usize, UserStoreError>;
+    /// The user's roles and the permissions they grant.
+    async fn get_roles(&self, user_id: &UserId) -> Result<UserRoles, UserStoreError>;
+    /// Give the user `role`; returns false if they already had it.
+    async fn assign_role(&mut self, user_id: &UserId, role: &Role) -> Result<bool, UserStoreError>;
+    /// Take `role` away from the user; returns false if they didn't have it.
+    async fn remove_role(&mut self, user_id: &UserId, role: &Role) -> Result<bool, UserStoreError>;
+    /// Every defined role with its permissions.
+    async fn list_roles(&self) -> Result<Vec<RoleDefinition>, UserStoreError>;
+    /// A page of users ordered by email, optionally only those whose email contains `search`
+    /// (case-insensitively).
+    async fn list_users(
+        &self,
+        search: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<UserPage, UserStoreError>;
+    async fn set_disabled(
+        &mut self,
+        user_id: &UserId,
+        disabled: bool,
+    ) -> Result<(), UserStoreError>;
+    /// Refuse password logins until `update_password` is called, which clears the flag.
+    async fn require_password_reset(&mut self, user_id: &UserId) -> Result<(), UserStoreError>;
+    async fn set_requires_2fa(
+        &mut self,
+        user_id: &UserId,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError>;
+    /// Delete the user along with their recovery codes, passkeys and roles.
+    async fn delete_user(&mut self, user_id: &UserId) -> Result<(), UserStoreError>;
+    /// Mark the user for deletion once `grace_seconds` have passed.
+    /// Returns when that is, as a Unix timestamp.
+    async fn schedule_deletion(
+        &mut self,
+        user_id: &UserId,
+        grace_seconds: u64,
+    ) -> Result<i64, UserStoreError>;
+    /// Keep the user after all; returns false if no deletion was pending.
+    async fn cancel_deletion(&mut self, user_id: &UserId) -> Result<bool, UserStoreError>;
+    /// Users whose deletion grace period is over.
+    async fn due_deletions(&self) -> Result<Vec<UserId>, UserStoreError>;
+    /// Remember that the user wants to switch to `new_email`, replacing any earlier request.
+    /// Fails with `UserAlreadyExists` if the address is taken or another user is switching to it.
+    async fn request_email_change(
+        &mut self,
+        user_id: &UserId,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError>;
+    /// Drop the pending switch; returns false if none was pending.
+    async fn cancel_email_change(&mut self, user_id: &UserId) -> Result<bool, UserStoreError>;
+    /// Switch the user who asked for `new_email` over to it and mark it verified, keeping the
+    /// address they leave for `EMAIL_CHANGE_REVERT_SECONDS`. Returns who that was and the address
+    /// that can undo the change: the one left now, or the one left first if a change is still
+    /// undoable.
+    async fn confirm_email_change(
+        &mut self,
+        new_email: &Email,
+    ) -> Result<(UserId, Email), UserStoreError>;
+    /// Move the user who left `previous_email` less than `EMAIL_CHANGE_REVERT_SECONDS` ago back
+    /// to it. Returns who that was.
+    async fn revert_email_change(
+        &mut self,
+        previous_email: &Email,
+    ) -> Result<UserId, UserStoreError>;
+}
+
+/// One page of `UserStore::list_users`.
+#[derive(Debug)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    /// Number of users matching the search across all pages
+    pub total: u64,
 }
 
 #[derive(Debug, Error)]
@@ -25,6 +154,8 @@ pub enum UserStoreError {
     UserNotFound,
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error("Role not found")]
+    RoleNotFound,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
@@ -36,24 +167,53 @@ impl PartialEq for UserStoreError {
             (Self::UserAlreadyExists, Self::UserAlreadyExists)
                 | (Self::UserNotFound, Self::UserNotFound)
                 | (Self::InvalidCredentials, Self::InvalidCredentials)
+                | (Self::RoleNotFound, Self::RoleNotFound)
                 | (Self::UnexpectedError(_), Self::UnexpectedError(_))
         )
     }
 }
 
 #[async_trait::async_trait]
-pub trait BannedTokenStore: Send + Sync {
-    async fn add_banned_token(&mut self, token: &str) -> Result<(), BannedTokenStoreError>;
-    async fn is_token_banned(&self, token: &str) -> Result<bool, BannedTokenStoreError>;
+pub trait BannedTokenStore: PersonalDataExporter {
+    /// Ban a token by its `jti`, or a session by its id, until `expires_at` (a Unix timestamp).
+    /// Past that point the token is rejected as expired anyway, so the ban needn't outlive it.
+    async fn add_banned_token(
+        &mut self,
+        token_id: &str,
+        expires_at: usize,
+    ) -> Result<(), BannedTokenStoreError>;
+    async fn is_token_banned(&self, token_id: &str) -> Result<bool, BannedTokenStoreError>;
 
     /// Atomically ensure token is banned; returns Ok(true) if newly inserted, Ok(false) if already banned.
-    async fn ban_if_not_present(&mut self, token: &str) -> Result<bool, BannedTokenStoreError> {
-        if self.is_token_banned(token).await? {
+    async fn ban_if_not_present(
+        &mut self,
+        token_id: &str,
+        expires_at: usize,
+    ) -> Result<bool, BannedTokenStoreError> {
+        if self.is_token_banned(token_id).await? {
             return Ok(false);
         }
-        self.add_banned_token(token).await?;
+        self.add_banned_token(token_id, expires_at).await?;
         Ok(true)
     }
+
+    /// Generation of the user's tokens; JWTs carrying an older one are no longer accepted.
+    /// Users start at generation 0.
+    async fn get_token_version(&self, user_id: &UserId) -> Result<u64, BannedTokenStoreError>;
+
+    /// Invalidate every JWT issued to the user so far and return the new generation.
+    async fn increment_token_version(
+        &mut self,
+        user_id: &UserId,
+    ) -> Result<u64, BannedTokenStoreError>;
+
+    /// Forget the user's generation after `ttl_seconds`, once the user is gone.
+    /// Must not be sooner than any JWT already issued to them expires.
+    async fn expire_token_version(
+        &mut self,
+        user_id: &UserId,
+        ttl_seconds: u64,
+    ) -> Result<(), BannedTokenStoreError>;
 }
 
 #[derive(Debug, Error)]
@@ -66,17 +226,17 @@ pub enum BannedTokenStoreError {
 // LoginAttemptId and TwoFACode are wrappers around a string type, similar to the Email and Password types.
 
 #[async_trait::async_trait]
-pub trait TwoFACodeStore {
+pub trait TwoFACodeStore: PersonalDataExporter {
     async fn add_code(
         &mut self,
-        email: Email,
+        user_id: UserId,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError>;
-    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError>;
+    async fn remove_code(&mut self, user_id: &UserId) -> Result<(), TwoFACodeStoreError>;
     async fn get_code(
         &self,
-        email: &Email,
+        user_id: &UserId,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
 }
 
@@ -125,7 +285,7 @@ pub struct TwoFACode(String);
 
 impl TwoFACode {
     pub fn parse(code: &str) -> Result<Self> {
-        if code.len() == 6 && code.chars().all(|c| c.is_digit(10)) {
+        if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
             Ok(Self(code.to_string()))
         } else {
             Err(eyre!("Invalid 2FA code format"))
@@ -147,3 +307,398 @@ impl AsRef<str> for TwoFACode {
         &self.0
     }
 }
+
+#[async_trait::async_trait]
+pub trait RefreshTokenStore: PersonalDataExporter {
+    async fn add_token(
+        &mut self,
+        token: &RefreshToken,
+        family: &RefreshTokenFamily,
+    ) -> Result<(), RefreshTokenStoreError>;
+    async fn get_family(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenFamily, RefreshTokenStoreError>;
+    async fn get_current_token(
+        &self,
+        family_id: &str,
+    ) -> Result<RefreshToken, RefreshTokenStoreError>;
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError>;
+    async fn revoke_all_families(&mut self, user_id: &UserId)
+        -> Result<(), RefreshTokenStoreError>;
+    /// Revoke every family of the user except `keep_family_id`, e.g. the caller's own session.
+    async fn revoke_other_families(
+        &mut self,
+        user_id: &UserId,
+        keep_family_id: &str,
+    ) -> Result<(), RefreshTokenStoreError>;
+
+    /// Exchange `presented` for `replacement` within the same family.
+    /// Presenting anything but the family's current token is treated as reuse and revokes the family.
+    /// Must be atomic, so that of two refreshes racing with the same token only one succeeds.
+    async fn rotate(
+        &mut self,
+        presented: &RefreshToken,
+        replacement: &RefreshToken,
+    ) -> Result<RefreshTokenFamily, RefreshTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum RefreshTokenStoreError {
+    #[error("Refresh token not found")]
+    TokenNotFound,
+    #[error("Refresh token reused")]
+    TokenReused,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for RefreshTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::TokenReused, Self::TokenReused)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+const REFRESH_TOKEN_LENGTH: usize = 43;
+
+// Alphanumeric string with ~5.95 bits of entropy per character
+fn random_token(length: usize) -> Secret<String> {
+    use rand::{distributions::Alphanumeric, Rng};
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect();
+    Secret::new(token)
+}
+
+/// Opaque refresh token handed to the client; only its server-side record gives it meaning.
+#[derive(Debug, Clone)]
+pub struct RefreshToken(Secret<String>);
+
+impl RefreshToken {
+    pub fn parse(s: Secret<String>) -> Result<Self> {
+        let inner = s.expose_secret();
+        if inner.len() == REFRESH_TOKEN_LENGTH && inner.chars().all(|c| c.is_ascii_alphanumeric()) {
+            Ok(Self(s))
+        } else {
+            Err(eyre!("Invalid refresh token format"))
+        }
+    }
+}
+
+impl Default for RefreshToken {
+    fn default() -> Self {
+        Self(random_token(REFRESH_TOKEN_LENGTH))
+    }
+}
+
+impl PartialEq for RefreshToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl AsRef<Secret<String>> for RefreshToken {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+/// A chain of refresh tokens descending from a single login.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshTokenFamily {
+    /// Also the id of the session the family belongs to
+    pub id: String,
+    pub user_id: UserId,
+}
+
+impl RefreshTokenFamily {
+    pub fn new(user_id: UserId) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id,
+        }
+    }
+}
+
+/// Tokens are tied to the address they were sent to, so they stop working once the user changes it.
+#[async_trait::async_trait]
+pub trait OneTimeTokenStore: PersonalDataExporter {
+    async fn add_token(
+        &mut self,
+        purpose: TokenPurpose,
+        token: &OneTimeToken,
+        email: &Email,
+    ) -> Result<(), OneTimeTokenStoreError>;
+    /// Redeem the token, returning the email it was issued for. A token can be consumed only once.
+    async fn consume_token(
+        &mut self,
+        purpose: TokenPurpose,
+        token: &OneTimeToken,
+    ) -> Result<Email, OneTimeTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum OneTimeTokenStoreError {
+    #[error("Token not found")]
+    TokenNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for OneTimeTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait::async_trait]
+pub trait PasskeyStore: PersonalDataExporter {
+    async fn add_credential(
+        &mut self,
+        credential: PasskeyCredential,
+    ) -> Result<(), PasskeyStoreError>;
+    async fn get_credential(&self, id: &[u8]) -> Result<PasskeyCredential, PasskeyStoreError>;
+    async fn get_credentials(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<PasskeyCredential>, PasskeyStoreError>;
+    async fn update_sign_count(
+        &mut self,
+        id: &[u8],
+        sign_count: u32,
+    ) -> Result<(), PasskeyStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum PasskeyStoreError {
+    #[error("Credential already exists")]
+    CredentialAlreadyExists,
+    #[error("Credential not found")]
+    CredentialNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for PasskeyStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::CredentialAlreadyExists, Self::CredentialAlreadyExists)
+                | (Self::CredentialNotFound, Self::CredentialNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait::async_trait]
+pub trait AuditEventStore: PersonalDataExporter {
+    async fn record_event(
+        &mut self,
+        user_id: &UserId,
+        kind: AuditEventKind,
+        client: &ClientInfo,
+    ) -> Result<(), AuditEventStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum AuditEventStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[async_trait::async_trait]
+pub trait ConsentStore: PersonalDataExporter {
+    /// Record the user's answer for `purpose`, replacing any earlier one.
+    async fn set_consent(
+        &mut self,
+        user_id: &UserId,
+        purpose: &ConsentPurpose,
+        granted: bool,
+    ) -> Result<(), ConsentStoreError>;
+    /// The user's answers, sorted by purpose.
+    async fn get_consents(&self, user_id: &UserId) -> Result<Vec<Consent>, ConsentStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum ConsentStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[async_trait::async_trait]
+pub trait SessionStore: PersonalDataExporter {
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError>;
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError>;
+    /// The user's sessions, most recently active first.
+    async fn get_sessions(&self, user_id: &UserId) -> Result<Vec<Session>, SessionStoreError>;
+    /// Record activity on a session, e.g. a token refresh.
+    async fn touch_session(
+        &mut self,
+        id: &str,
+        client: &ClientInfo,
+    ) -> Result<(), SessionStoreError>;
+    async fn remove_session(&mut self, id: &str) -> Result<(), SessionStoreError>;
+    async fn remove_all_sessions(&mut self, user_id: &UserId) -> Result<(), SessionStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum SessionStoreError {
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for SessionStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::SessionNotFound, Self::SessionNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait::async_trait]
+pub trait RateLimitStore: PersonalDataExporter {
+    /// Count an attempt against `key`. Over the limit the attempt is not counted, and the time
+    /// until the next one would be allowed is returned instead.
+    async fn record_attempt(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<Option<Duration>, RateLimitStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum RateLimitStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[async_trait::async_trait]
+pub trait MagicLinkStore: PersonalDataExporter {
+    /// Store a login link for `email`, redeemable only by the browser holding `nonce`.
+    async fn add_link(
+        &mut self,
+        token: &OneTimeToken,
+        email: &Email,
+        nonce: &OneTimeToken,
+    ) -> Result<(), MagicLinkStoreError>;
+    /// Redeem the link, returning the email it was issued for. A link can be consumed only once;
+    /// presenting the wrong nonce leaves it usable by the browser that asked for it.
+    async fn consume_link(
+        &mut self,
+        token: &OneTimeToken,
+        nonce: &OneTimeToken,
+    ) -> Result<Email, MagicLinkStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum MagicLinkStoreError {
+    #[error("Link not found")]
+    LinkNotFound,
+    #[error("Link was requested from another browser")]
+    NonceMismatch,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for MagicLinkStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::LinkNotFound, Self::LinkNotFound)
+                | (Self::NonceMismatch, Self::NonceMismatch)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+/// What a one-time token may be redeemed for; a token issued for one purpose is useless for another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+    /// WebAuthn challenges; the token doubles as the challenge the authenticator signs
+    PasskeyRegistration,
+    PasskeyAuthentication,
+    /// Emailed when the user asks for their account to be deleted
+    CancelAccountDeletion,
+    /// Sent to the new address of an email change, which it confirms
+    ConfirmEmailChange,
+    /// Sent to the old address of an email change, so its owner can stop it or undo it
+    CancelEmailChange,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::PasskeyRegistration => "passkey_registration",
+            TokenPurpose::PasskeyAuthentication => "passkey_authentication",
+            TokenPurpose::CancelAccountDeletion => "cancel_account_deletion",
+            TokenPurpose::ConfirmEmailChange => "confirm_email_change",
+            TokenPurpose::CancelEmailChange => "cancel_email_change",
+        }
+    }
+
+    /// How long an issued token stays redeemable.
+    pub fn ttl_seconds(&self) -> u64 {
+        match self {
+            TokenPurpose::PasswordReset => 60 * 30, // 30 minutes
+            TokenPurpose::EmailVerification | TokenPurpose::ConfirmEmailChange => 60 * 60 * 24, // 24 hours
+            TokenPurpose::PasskeyRegistration | TokenPurpose::PasskeyAuthentication => 60 * 5, // 5 minutes
+            TokenPurpose::CancelAccountDeletion => ACCOUNT_DELETION_GRACE_SECONDS,
+            TokenPurpose::CancelEmailChange => EMAIL_CHANGE_REVERT_SECONDS,
+        }
+    }
+}
+
+const ONE_TIME_TOKEN_LENGTH: usize = 43;
+
+/// Single-use secret delivered out of band (e.g. in an emailed link).
+#[derive(Debug, Clone)]
+pub struct OneTimeToken(Secret<String>);
+
+impl OneTimeToken {
+    pub fn parse(s: Secret<String>) -> Result<Self> {
+        let inner = s.expose_secret();
+        if inner.len() == ONE_TIME_TOKEN_LENGTH && inner.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            Ok(Self(s))
+        } else {
+            Err(eyre!("Invalid one-time token format"))
+        }
+    }
+}
+
+impl Default for OneTimeToken {
+    fn default() -> Self {
+        Self(random_token(ONE_TIME_TOKEN_LENGTH))
+    }
+}
+
+impl PartialEq for OneTimeToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl AsRef<Secret<String>> for OneTimeToken {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
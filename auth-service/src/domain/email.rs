@@ -21,15 +21,24 @@ impl PartialEq for Email {
 impl Eq for Email {}
 
 impl Email {
+    /// Parse an address into its canonical form, so that spellings of the same mailbox compare
+    /// equal: surrounding whitespace is trimmed, the address is lowercased and the domain is
+    /// converted to punycode.
     pub fn parse(s: Secret<String>) -> Result<Email> {
-        if validate_email(s.expose_secret()) {
-            Ok(Self(s))
-        } else {
-            Err(eyre!("{} is not a valid email.", s.expose_secret()))
+        match normalize(s.expose_secret()) {
+            Some(email) if validate_email(&email) => Ok(Self(Secret::new(email))),
+            _ => Err(eyre!("{} is not a valid email.", s.expose_secret())),
         }
     }
 }
 
+// Local parts are case-sensitive by the RFC, but practically no mail server treats them that way
+fn normalize(s: &str) -> Option<String> {
+    let (local_part, domain) = s.trim().rsplit_once('@')?;
+    let domain = idna::domain_to_ascii(domain).ok()?;
+    Some(format!("{}@{}", local_part.to_lowercase(), domain))
+}
+
 // Manual impls for sqlx traits
 impl<'r> Decode<'r, Postgres> for Email {
     fn decode(value: PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
@@ -133,6 +142,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_surrounding_whitespace_is_trimmed() {
+        let email = Email::parse(Secret::new("  user@example.com\n".to_string())).unwrap();
+        assert_eq!(email.as_ref().expose_secret(), "user@example.com");
+    }
+
+    #[test]
+    fn test_case_differences_are_the_same_email() {
+        let email = Email::parse(Secret::new("Alice@Example.COM".to_string())).unwrap();
+        assert_eq!(email.as_ref().expose_secret(), "alice@example.com");
+        assert_eq!(
+            email,
+            Email::parse(Secret::new("alice@example.com".to_string())).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_international_domains_become_punycode() {
+        let email = Email::parse(Secret::new("user@Bücher.example".to_string())).unwrap();
+        assert_eq!(email.as_ref().expose_secret(), "user@xn--bcher-kva.example");
+        assert_eq!(
+            email,
+            Email::parse(Secret::new("user@xn--bcher-kva.example".to_string())).unwrap()
+        );
+    }
+
     #[test]
     fn test_email_access() {
         let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
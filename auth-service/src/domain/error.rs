@@ -1,10 +1,14 @@
 use axum::{
-    http::StatusCode,
+    http::{
+        header::{RETRY_AFTER, WWW_AUTHENTICATE},
+        StatusCode,
+    },
     response::{IntoResponse, Response},
     Json,
 };
 use color_eyre::eyre::Report;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -19,6 +23,26 @@ pub enum AuthAPIError {
     MissingToken,
     #[error("Invalid token")]
     InvalidToken,
+    #[error("Email not verified")]
+    EmailNotVerified,
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Invalid client")]
+    InvalidClient,
+    #[error("Invalid audience")]
+    InvalidAudience,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Account disabled")]
+    AccountDisabled,
+    #[error("Password reset required")]
+    PasswordResetRequired,
+    #[error("Account pending deletion")]
+    AccountPendingDeletion,
+    #[error("Too many requests")]
+    TooManyRequests { retry_after: Duration },
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
@@ -39,6 +63,43 @@ impl IntoResponse for AuthAPIError {
             }
             AuthAPIError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
             AuthAPIError::MissingToken => (StatusCode::BAD_REQUEST, "Missing token"),
+            AuthAPIError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email not verified"),
+            AuthAPIError::SessionNotFound => (StatusCode::NOT_FOUND, "Session not found"),
+            AuthAPIError::InvalidAudience => (StatusCode::BAD_REQUEST, "Invalid audience"),
+            AuthAPIError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
+            AuthAPIError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
+            AuthAPIError::AccountDisabled => (StatusCode::FORBIDDEN, "Account disabled"),
+            AuthAPIError::PasswordResetRequired => {
+                (StatusCode::FORBIDDEN, "Password reset required")
+            }
+            AuthAPIError::AccountPendingDeletion => {
+                (StatusCode::FORBIDDEN, "Account pending deletion")
+            }
+            AuthAPIError::InvalidClient => {
+                // RFC 6749 section 5.2: tell the client which scheme to authenticate with
+                let body = Json(ErrorResponse {
+                    error: "Invalid client".to_owned(),
+                });
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(WWW_AUTHENTICATE, "Basic")],
+                    body,
+                )
+                    .into_response();
+            }
+            AuthAPIError::TooManyRequests { retry_after } => {
+                let body = Json(ErrorResponse {
+                    error: "Too many requests".to_owned(),
+                });
+                // Whole seconds, rounded up so a client waiting that long is let through
+                let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, seconds.max(1).to_string())],
+                    body,
+                )
+                    .into_response();
+            }
             AuthAPIError::UnexpectedError(_) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
             }
@@ -0,0 +1,41 @@
+use color_eyre::eyre::{eyre, Report};
+use std::str::FromStr;
+
+/// What `login` does with accounts whose email address has not been verified yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnverifiedLoginPolicy {
+    /// Unverified accounts log in like any other.
+    Allow,
+    /// Unverified accounts are refused until the verification link has been followed.
+    Deny,
+}
+
+impl FromStr for UnverifiedLoginPolicy {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "allow" => Ok(UnverifiedLoginPolicy::Allow),
+            "deny" => Ok(UnverifiedLoginPolicy::Deny),
+            other => Err(eyre!("unknown unverified login policy: {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!(
+            "allow".parse::<UnverifiedLoginPolicy>().unwrap(),
+            UnverifiedLoginPolicy::Allow
+        );
+        assert_eq!(
+            " DENY ".parse::<UnverifiedLoginPolicy>().unwrap(),
+            UnverifiedLoginPolicy::Deny
+        );
+        assert!("sometimes".parse::<UnverifiedLoginPolicy>().is_err());
+    }
+}
@@ -1,13 +1,35 @@
+mod api_client;
+mod audit;
+mod consent;
 mod data_stores;
 mod email;
 mod email_client;
 mod error;
+mod login_policy;
+mod passkey;
 mod password;
+mod personal_data;
+mod rate_limit;
+mod recovery_code;
+mod role;
+mod session;
+mod totp;
 mod user;
 
+pub use api_client::*;
+pub use audit::*;
+pub use consent::*;
 pub use data_stores::*;
 pub use email::*;
 pub use email_client::*;
 pub use error::*;
+pub use login_policy::*;
+pub use passkey::*;
 pub use password::*;
+pub use personal_data::*;
+pub use rate_limit::*;
+pub use recovery_code::*;
+pub use role::*;
+pub use session::*;
+pub use totp::*;
 pub use user::*;
@@ -0,0 +1,105 @@
+use color_eyre::eyre::{eyre, Result};
+use ring::signature::{self, UnparsedPublicKey};
+
+use super::UserId;
+
+// COSE algorithm identifiers (RFC 9053) that we offer to authenticators
+pub const COSE_ALG_ES256: i64 = -7;
+pub const COSE_ALG_EDDSA: i64 = -8;
+
+/// Public key of a passkey, as registered by the authenticator.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasskeyPublicKey {
+    /// ECDSA P-256 with SHA-256; uncompressed SEC1 point
+    Es256(Vec<u8>),
+    /// Ed25519; raw 32 byte key
+    EdDsa(Vec<u8>),
+}
+
+impl PasskeyPublicKey {
+    pub fn from_parts(algorithm: i64, key: Vec<u8>) -> Result<Self> {
+        match algorithm {
+            COSE_ALG_ES256 if key.len() == 65 && key[0] == 0x04 => Ok(Self::Es256(key)),
+            COSE_ALG_EDDSA if key.len() == 32 => Ok(Self::EdDsa(key)),
+            alg => Err(eyre!("unsupported or malformed passkey key (alg {})", alg)),
+        }
+    }
+
+    pub fn algorithm(&self) -> i64 {
+        match self {
+            Self::Es256(_) => COSE_ALG_ES256,
+            Self::EdDsa(_) => COSE_ALG_EDDSA,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Es256(key) | Self::EdDsa(key) => key,
+        }
+    }
+
+    /// Check an assertion signature. ES256 signatures come DER encoded, as WebAuthn specifies.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        let algorithm: &dyn signature::VerificationAlgorithm = match self {
+            Self::Es256(_) => &signature::ECDSA_P256_SHA256_ASN1,
+            Self::EdDsa(_) => &signature::ED25519,
+        };
+        UnparsedPublicKey::new(algorithm, self.as_bytes())
+            .verify(message, signature)
+            .map_err(|_| eyre!("invalid passkey signature"))
+    }
+}
+
+/// A registered passkey of a user.
+#[derive(Debug, Clone)]
+pub struct PasskeyCredential {
+    pub id: Vec<u8>,
+    pub user_id: UserId,
+    pub public_key: PasskeyPublicKey,
+    pub sign_count: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::{
+        rand::SystemRandom,
+        signature::{EcdsaKeyPair, Ed25519KeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING},
+    };
+
+    #[test]
+    fn test_verify_es256() {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                .unwrap();
+        let key =
+            PasskeyPublicKey::from_parts(COSE_ALG_ES256, key_pair.public_key().as_ref().to_vec())
+                .unwrap();
+
+        let signature = key_pair.sign(&rng, b"message").unwrap();
+        assert!(key.verify(b"message", signature.as_ref()).is_ok());
+        assert!(key.verify(b"other", signature.as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_verify_eddsa() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let key =
+            PasskeyPublicKey::from_parts(COSE_ALG_EDDSA, key_pair.public_key().as_ref().to_vec())
+                .unwrap();
+
+        let signature = key_pair.sign(b"message");
+        assert!(key.verify(b"message", signature.as_ref()).is_ok());
+        assert!(key.verify(b"other", signature.as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_from_parts_rejects_unsupported_keys() {
+        assert!(PasskeyPublicKey::from_parts(-257, vec![0; 256]).is_err());
+        assert!(PasskeyPublicKey::from_parts(COSE_ALG_ES256, vec![0; 65]).is_err());
+        assert!(PasskeyPublicKey::from_parts(COSE_ALG_EDDSA, vec![0; 31]).is_err());
+    }
+}
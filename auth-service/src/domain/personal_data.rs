@@ -0,0 +1,15 @@
+use color_eyre::eyre::Report;
+use serde_json::Value;
+
+use super::UserId;
+
+/// A store's part of a user's data export (GDPR data portability).
+/// Every store trait requires it, so a store added later can't be left out of `/account/export`.
+#[async_trait::async_trait]
+pub trait PersonalDataExporter: Send + Sync {
+    /// Key of the store's section in the export, e.g. `sessions`.
+    fn export_section(&self) -> &'static str;
+    /// What the store holds on the user, leaving out secrets such as password hashes and tokens.
+    /// `None` if it keeps nothing but such secrets.
+    async fn export_personal_data(&self, user_id: &UserId) -> Result<Option<Value>, Report>;
+}
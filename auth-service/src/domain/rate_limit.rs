@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+const FIFTEEN_MINUTES: Duration = Duration::from_secs(60 * 15);
+
+/// At most `max_attempts` within any `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_attempts: u32,
+    pub window: Duration,
+}
+
+impl RateLimit {
+    pub const fn new(max_attempts: u32, window: Duration) -> Self {
+        Self {
+            max_attempts,
+            window,
+        }
+    }
+}
+
+/// Limits for a group of routes. Each client address and each email gets its own allowance,
+/// shared by every route in the group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteRateLimit {
+    /// Names the group in the keys of its attempt counters
+    pub name: &'static str,
+    pub per_ip: RateLimit,
+    pub per_email: RateLimit,
+}
+
+/// Rate limits of every route group that has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Password logins, with or without cookies
+    pub login: RouteRateLimit,
+    /// 2FA codes are only 6 digits, so an email gets fewer tries than for its password
+    pub verify_2fa: RouteRateLimit,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            login: RouteRateLimit {
+                name: "login",
+                per_ip: RateLimit::new(50, FIFTEEN_MINUTES),
+                per_email: RateLimit::new(10, FIFTEEN_MINUTES),
+            },
+            verify_2fa: RouteRateLimit {
+                name: "verify_2fa",
+                per_ip: RateLimit::new(50, FIFTEEN_MINUTES),
+                per_email: RateLimit::new(5, FIFTEEN_MINUTES),
+            },
+        }
+    }
+}
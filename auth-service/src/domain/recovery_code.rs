@@ -0,0 +1,111 @@
+use color_eyre::eyre::{eyre, Result};
+use secrecy::{ExposeSecret, Secret};
+
+/// How many recovery codes a user holds after (re)generation.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const RECOVERY_CODE_LENGTH: usize = 10;
+// Lowercase letters and digits without the easily confused 0/o, 1/l and i
+const RECOVERY_CODE_ALPHABET: &[u8] = b"23456789abcdefghjkmnpqrstuvwxyz";
+
+/// Single-use code that stands in for a 2FA code when the second factor is unavailable.
+/// Shown to users as two groups of five, e.g. `k7m2p-x9qwe`.
+#[derive(Debug, Clone)]
+pub struct RecoveryCode(Secret<String>);
+
+impl RecoveryCode {
+    /// Accepts codes as shown or as typed: dashes, spaces and case are ignored.
+    pub fn parse(s: &str) -> Result<Self> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        if normalized.len() != RECOVERY_CODE_LENGTH
+            || !normalized
+                .bytes()
+                .all(|b| RECOVERY_CODE_ALPHABET.contains(&b))
+        {
+            return Err(eyre!("Invalid recovery code format"));
+        }
+        Ok(Self(Secret::new(normalized)))
+    }
+
+    /// Generate a fresh set of `RECOVERY_CODE_COUNT` codes.
+    pub fn generate_set() -> Vec<RecoveryCode> {
+        (0..RECOVERY_CODE_COUNT)
+            .map(|_| RecoveryCode::default())
+            .collect()
+    }
+
+    /// The code in its display form.
+    pub fn display(&self) -> String {
+        let code = self.0.expose_secret();
+        let (first, second) = code.split_at(RECOVERY_CODE_LENGTH / 2);
+        format!("{}-{}", first, second)
+    }
+}
+
+/// An unused recovery code as stored: the id of its row and the hash of the code.
+#[derive(Debug, Clone)]
+pub struct StoredRecoveryCode {
+    pub id: i64,
+    pub code_hash: Secret<String>,
+}
+
+impl AsRef<Secret<String>> for RecoveryCode {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+impl Default for RecoveryCode {
+    fn default() -> Self {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        let code = (0..RECOVERY_CODE_LENGTH)
+            .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+            .collect();
+        Self(Secret::new(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_form_parses_back() {
+        let code = RecoveryCode::default();
+        let shown = code.display();
+        assert_eq!(shown.len(), RECOVERY_CODE_LENGTH + 1);
+        let parsed = RecoveryCode::parse(&shown).unwrap();
+        assert_eq!(
+            parsed.as_ref().expose_secret(),
+            code.as_ref().expose_secret()
+        );
+    }
+
+    #[test]
+    fn test_parse_normalizes_input() {
+        let parsed = RecoveryCode::parse(" K7M2P x9QWE ").unwrap();
+        assert_eq!(parsed.as_ref().expose_secret(), "k7m2px9qwe");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_codes() {
+        for code in ["", "123456", "k7m2p-x9qw", "k7m2p-x9qwe1", "k7m2p-x9qw0"] {
+            assert!(
+                RecoveryCode::parse(code).is_err(),
+                "{} should be rejected",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_set() {
+        let codes = RecoveryCode::generate_set();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+    }
+}
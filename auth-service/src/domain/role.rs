@@ -0,0 +1,71 @@
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+/// Role that may manage other users through the admin API.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// A role routes can demand of the caller with `RequireRole`.
+pub trait RequiredRole {
+    const NAME: &'static str;
+}
+
+/// Marker for the admin role.
+pub struct AdminRole;
+
+impl RequiredRole for AdminRole {
+    const NAME: &'static str = ADMIN_ROLE;
+}
+
+/// Name of a role from the `roles` table, e.g. `admin`.
+/// Lowercase letters, digits, `-` and `_`, so it never needs escaping in a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Role(String);
+
+impl Role {
+    pub fn parse(s: &str) -> Result<Self> {
+        let valid = !s.is_empty()
+            && s.len() <= 64
+            && s.bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+        if !valid {
+            return Err(eyre!("Invalid role name"));
+        }
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl AsRef<str> for Role {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The roles a user holds and every permission they grant, each sorted and without duplicates.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserRoles {
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+/// A role as defined in the database, with the permissions it grants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoleDefinition {
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_role() {
+        assert_eq!(Role::parse("admin").unwrap().as_ref(), "admin");
+        assert!(Role::parse("support_agent-2").is_ok());
+        assert!(Role::parse("").is_err());
+        assert!(Role::parse("Admin").is_err());
+        assert!(Role::parse("admin user").is_err());
+        assert!(Role::parse(&"a".repeat(65)).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts},
+};
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Report};
+use ipnet::IpNet;
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use super::UserId;
+
+/// Where a request came from, as recorded for a session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    TrustedProxies: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        // nginx puts the address it saw in X-Real-IP; anyone else could put anything there
+        let ip = match peer {
+            Some(peer) if TrustedProxies::from_ref(state).contains(&peer) => {
+                header("x-real-ip").or_else(|| Some(peer.to_string()))
+            }
+            peer => peer.map(|peer| peer.to_string()),
+        };
+
+        Ok(Self {
+            ip,
+            user_agent: header(USER_AGENT.as_str()),
+        })
+    }
+}
+
+/// Addresses of the reverse proxies whose `X-Real-IP` header is believed. Empty unless configured.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    pub fn new(networks: Vec<IpNet>) -> Self {
+        Self(networks)
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.iter().any(|network| network.contains(ip))
+    }
+}
+
+/// Parses addresses and CIDR ranges separated by commas, e.g. `10.0.0.5,172.16.0.0/12`.
+impl FromStr for TrustedProxies {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| eyre!("{} is neither an IP address nor a CIDR range", entry))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+/// A login on one device. The id is that of the session's refresh token family
+/// and is carried as `sid` in every JWT the session is issued.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    pub user_id: UserId,
+    pub device: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Unix timestamps in seconds
+    pub created_at: i64,
+    pub last_seen_at: i64,
+}
+
+impl Session {
+    pub fn new(id: String, user_id: UserId, client: &ClientInfo) -> Self {
+        let now = Utc::now().timestamp();
+        Self {
+            id,
+            user_id,
+            device: describe_device(client.user_agent.as_deref()),
+            ip: client.ip.clone(),
+            user_agent: client.user_agent.clone(),
+            created_at: now,
+            last_seen_at: now,
+        }
+    }
+
+    /// Note activity from `client`, e.g. a token refresh.
+    pub fn touch(&mut self, client: &ClientInfo) {
+        self.last_seen_at = Utc::now().timestamp();
+        if client.ip.is_some() {
+            self.ip = client.ip.clone();
+        }
+    }
+}
+
+// Checked in order: Edge and Opera also claim to be Chrome, Chrome also claims to be Safari,
+// Android also claims Linux and iOS also claims macOS.
+const BROWSERS: [(&str, &str); 5] = [
+    ("Edg/", "Edge"),
+    ("OPR/", "Opera"),
+    ("Firefox/", "Firefox"),
+    ("Chrome/", "Chrome"),
+    ("Safari/", "Safari"),
+];
+const PLATFORMS: [(&str, &str); 7] = [
+    ("Android", "Android"),
+    ("iPhone", "iOS"),
+    ("iPad", "iOS"),
+    ("Windows", "Windows"),
+    ("Mac OS X", "macOS"),
+    ("CrOS", "ChromeOS"),
+    ("Linux", "Linux"),
+];
+
+/// Short human-readable description of the device behind a user agent, e.g. "Firefox on Linux".
+pub fn describe_device(user_agent: Option<&str>) -> String {
+    let find = |table: &[(&str, &'static str)]| {
+        user_agent.and_then(|ua| {
+            table
+                .iter()
+                .find(|(marker, _)| ua.contains(marker))
+                .map(|(_, name)| *name)
+        })
+    };
+
+    match (find(&BROWSERS), find(&PLATFORMS)) {
+        (Some(browser), Some(platform)) => format!("{} on {}", browser, platform),
+        (Some(name), None) | (None, Some(name)) => name.to_owned(),
+        (None, None) => "Unknown device".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_device() {
+        let cases = [
+            (
+                "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
+                "Firefox on Linux",
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                 Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0",
+                "Edge on Windows",
+            ),
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 \
+                 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+                "Safari on iOS",
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) \
+                 Chrome/126.0.0.0 Mobile Safari/537.36",
+                "Chrome on Android",
+            ),
+        ];
+        for (user_agent, expected) in cases {
+            assert_eq!(describe_device(Some(user_agent)), expected);
+        }
+    }
+
+    #[test]
+    fn test_describe_unknown_device() {
+        assert_eq!(describe_device(None), "Unknown device");
+        assert_eq!(describe_device(Some("curl/8.5.0")), "Unknown device");
+    }
+
+    #[test]
+    fn test_trusted_proxies_parse_addresses_and_ranges() {
+        let proxies: TrustedProxies = " 10.0.0.5, 172.16.0.0/12,::1 ".parse().unwrap();
+        assert!(proxies.contains(&"10.0.0.5".parse().unwrap()));
+        assert!(proxies.contains(&"172.20.3.4".parse().unwrap()));
+        assert!(proxies.contains(&"::1".parse().unwrap()));
+        assert!(!proxies.contains(&"10.0.0.6".parse().unwrap()));
+        assert!(!proxies.contains(&"192.168.1.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_trusted_proxies_reject_invalid_entries() {
+        assert_eq!(
+            "".parse::<TrustedProxies>().unwrap(),
+            TrustedProxies::default()
+        );
+        assert!("10.0.0.5,nginx".parse::<TrustedProxies>().is_err());
+        assert!("10.0.0.0/33".parse::<TrustedProxies>().is_err());
+    }
+}
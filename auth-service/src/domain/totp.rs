@@ -0,0 +1,215 @@
+use color_eyre::eyre::{eyre, Result};
+use data_encoding::BASE32_NOPAD;
+use ring::hmac;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgValueRef, Decode, Postgres, Type};
+
+use super::{Email, TwoFACode};
+
+// RFC 6238 defaults, which is what authenticator apps assume
+const TOTP_STEP_SECONDS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+// Accept the neighbouring steps to tolerate clock drift on the user's phone
+const TOTP_ALLOWED_SKEW_STEPS: u64 = 1;
+const TOTP_SECRET_BYTES: usize = 20;
+const TOTP_ISSUER: &str = "auth-service";
+
+/// How a user with 2FA enabled proves the second factor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TwoFAMethod {
+    /// A one-off code emailed at login
+    Email,
+    /// A code from an authenticator app (RFC 6238)
+    Totp,
+}
+
+impl TwoFAMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TwoFAMethod::Email => "email",
+            TwoFAMethod::Totp => "totp",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "email" => Ok(TwoFAMethod::Email),
+            "totp" => Ok(TwoFAMethod::Totp),
+            other => Err(eyre!("unknown 2FA method: {}", other)),
+        }
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for TwoFAMethod {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let s = <&str as Decode<Postgres>>::decode(value)?;
+        TwoFAMethod::parse(s).map_err(|e| e.into())
+    }
+}
+
+impl Type<Postgres> for TwoFAMethod {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+}
+
+/// Shared secret between the server and the user's authenticator app.
+pub struct TotpSecret(Secret<Vec<u8>>);
+
+impl std::fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TotpSecret([REDACTED])")
+    }
+}
+
+impl TotpSecret {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        if bytes.len() < 16 {
+            return Err(eyre!("TOTP secret must be at least 128 bits"));
+        }
+        Ok(Self(Secret::new(bytes)))
+    }
+
+    /// Base32 form that users type into their authenticator app.
+    pub fn to_base32(&self) -> Secret<String> {
+        Secret::new(BASE32_NOPAD.encode(self.0.expose_secret()))
+    }
+
+    /// Key URI understood by authenticator apps, usually shown as a QR code.
+    pub fn otpauth_uri(&self, account: &Email) -> Secret<String> {
+        let label = format!(
+            "{}:{}",
+            TOTP_ISSUER,
+            percent_encode(account.as_ref().expose_secret())
+        );
+        Secret::new(format!(
+            "otpauth://totp/{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            label,
+            self.to_base32().expose_secret(),
+            TOTP_ISSUER,
+            TOTP_DIGITS,
+            TOTP_STEP_SECONDS
+        ))
+    }
+
+    /// Check `code` against the steps around `unix_time`.
+    /// Returns the matching time step so callers can refuse to accept it twice.
+    pub fn verify(&self, code: &TwoFACode, unix_time: u64) -> Option<u64> {
+        let current = unix_time / TOTP_STEP_SECONDS;
+        (current.saturating_sub(TOTP_ALLOWED_SKEW_STEPS)..=current + TOTP_ALLOWED_SKEW_STEPS)
+            .find(|step| self.code_at(*step) == code.as_ref())
+    }
+
+    /// The code an authenticator app shows at `unix_time`.
+    pub fn generate(&self, unix_time: u64) -> TwoFACode {
+        TwoFACode::parse(&self.code_at(unix_time / TOTP_STEP_SECONDS))
+            .expect("TOTP codes are always six digits")
+    }
+
+    fn code_at(&self, step: u64) -> String {
+        // RFC 4226 HOTP with the time step as counter
+        let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, self.0.expose_secret());
+        let tag = hmac::sign(&key, &step.to_be_bytes());
+        let digest = tag.as_ref();
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(TOTP_DIGITS),
+            width = TOTP_DIGITS as usize
+        )
+    }
+}
+
+impl AsRef<Secret<Vec<u8>>> for TotpSecret {
+    fn as_ref(&self) -> &Secret<Vec<u8>> {
+        &self.0
+    }
+}
+
+impl Default for TotpSecret {
+    fn default() -> Self {
+        use rand::RngCore;
+        let mut bytes = vec![0u8; TOTP_SECRET_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(Secret::new(bytes))
+    }
+}
+
+// otpauth labels are URI path segments; keep unreserved characters and '@' readable
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B test vectors (SHA1, truncated to 6 digits)
+    fn rfc_secret() -> TotpSecret {
+        TotpSecret::from_bytes(b"12345678901234567890".to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let secret = rfc_secret();
+        for (time, expected) in [
+            (59u64, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(secret.code_at(time / TOTP_STEP_SECONDS), expected);
+        }
+    }
+
+    #[test]
+    fn test_verify_accepts_adjacent_steps_only() {
+        let secret = rfc_secret();
+        let code = TwoFACode::parse("081804").unwrap();
+        assert_eq!(secret.verify(&code, 1111111109), Some(1111111109 / 30));
+        assert!(secret.verify(&code, 1111111109 + 30).is_some());
+        assert!(secret.verify(&code, 1111111109 + 90).is_none());
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let secret = rfc_secret();
+        let email = Email::parse(Secret::new("user+tag@example.com".to_owned())).unwrap();
+        let uri = secret.otpauth_uri(&email);
+        assert_eq!(
+            uri.expose_secret(),
+            "otpauth://totp/auth-service:user%2Btag@example.com\
+             ?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=auth-service\
+             &algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn test_two_fa_method_round_trip() {
+        for method in [TwoFAMethod::Email, TwoFAMethod::Totp] {
+            assert_eq!(TwoFAMethod::parse(method.as_str()).unwrap(), method);
+        }
+        assert!(TwoFAMethod::parse("sms").is_err());
+    }
+
+    #[test]
+    fn test_secret_too_short() {
+        assert!(TotpSecret::from_bytes(vec![0; 10]).is_err());
+    }
+}
@@ -1,20 +1,96 @@
+use color_eyre::eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use std::fmt;
+use uuid::Uuid;
 
-use crate::domain::{Email, Password};
+use crate::domain::{Email, Password, TwoFAMethod};
+
+/// How long an account the user asked to delete can still be restored.
+pub const ACCOUNT_DELETION_GRACE_SECONDS: u64 = 60 * 60 * 24 * 30; // 30 days
+
+/// How long the old address of an email change can undo it, and stays reserved for the account.
+pub const EMAIL_CHANGE_REVERT_SECONDS: u64 = 60 * 60 * 24; // 24 hours
+
+/// Stable id of a user, carried as `sub` in their tokens. Unlike the email it never changes
+/// and says nothing about the person.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct UserId(Uuid);
+
+impl UserId {
+    pub fn parse(s: &str) -> Result<Self> {
+        let id = Uuid::parse_str(s).wrap_err("Invalid user id")?;
+        Ok(Self(id))
+    }
+}
+
+impl Default for UserId {
+    fn default() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl From<Uuid> for UserId {
+    fn from(id: Uuid) -> Self {
+        Self(id)
+    }
+}
+
+impl AsRef<Uuid> for UserId {
+    fn as_ref(&self) -> &Uuid {
+        &self.0
+    }
+}
+
+impl fmt::Display for UserId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 #[derive(Debug, Clone, FromRow)]
 pub struct User {
+    pub id: UserId,
     pub email: Email,
     pub password: Password,
     pub requires_2fa: bool,
+    pub email_verified: bool,
+    pub two_fa_method: TwoFAMethod,
+    /// Set by an admin; the user can't log in until re-enabled
+    pub disabled: bool,
+    /// Set by an admin; password logins are refused until the password is reset
+    pub password_reset_required: bool,
+    /// The user asked for the account to be deleted; it can't be logged into meanwhile
+    pub pending_deletion: bool,
 }
 
 impl User {
     pub fn new(email: Email, password: Password, requires_2fa: bool) -> Self {
         User {
+            id: UserId::default(),
             email,
             password,
             requires_2fa,
+            email_verified: false,
+            two_fa_method: TwoFAMethod::Email,
+            disabled: false,
+            password_reset_required: false,
+            pending_deletion: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_id() {
+        let id = UserId::default();
+        assert_eq!(UserId::parse(&id.to_string()).unwrap(), id);
+        assert!(UserId::parse("test@example.com").is_err());
+        assert!(UserId::parse("").is_err());
+    }
+}
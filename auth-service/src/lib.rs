@@ -1,10 +1,15 @@
 use crate::utils::{
-    env::ALLOWED_ORIGINS_ENV_VAR, make_span_with_request_id, on_request, on_response,
-    DEFAULT_ALLOWED_ORIGINS,
+    env::ALLOWED_ORIGINS_ENV_VAR, make_span_with_request_id, on_request, on_response, rate_limit,
+    RateLimiter, ADMIN_KEY_HEADER, DEFAULT_ALLOWED_ORIGINS,
 };
 use app_state::AppState;
 use axum::{
-    http::{HeaderValue, Method},
+    extract::{connect_info::IntoMakeServiceWithConnectInfo, ConnectInfo},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderName, HeaderValue, Method,
+    },
+    middleware::{from_fn_with_state, AddExtension},
     response::Html,
     routing::{get, post},
     serve::Serve,
@@ -13,7 +18,7 @@ use axum::{
 use redis::{Client, RedisResult};
 use secrecy::{ExposeSecret, Secret};
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::{env, error::Error};
+use std::{env, error::Error, net::SocketAddr};
 use tower_http::{
     cors::{Any, CorsLayer},
     services::ServeDir,
@@ -27,7 +32,10 @@ pub mod services;
 pub mod utils;
 
 pub struct Application {
-    server: Serve<Router, Router>,
+    server: Serve<
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        AddExtension<Router, ConnectInfo<SocketAddr>>,
+    >,
     pub address: String,
 }
 
@@ -40,7 +48,12 @@ impl Application {
         let cors = {
             let base = || {
                 CorsLayer::new()
-                    .allow_methods([Method::GET, Method::POST])
+                    .allow_methods([Method::GET, Method::POST, Method::DELETE])
+                    .allow_headers([
+                        AUTHORIZATION,
+                        CONTENT_TYPE,
+                        HeaderName::from_static(ADMIN_KEY_HEADER),
+                    ])
                     .allow_credentials(true)
             };
             match load_allowed_origins()? {
@@ -50,15 +63,111 @@ impl Application {
             }
         };
 
+        // Routes sharing a limit share its counters, e.g. both password logins
+        let rate_limit_store = app_state.rate_limit_store.clone();
+        let rate_limits = app_state.rate_limits;
+        let trusted_proxies = app_state.trusted_proxies.clone();
+        let rate_limited = |limit| {
+            from_fn_with_state(
+                RateLimiter::new(rate_limit_store.clone(), limit, trusted_proxies.clone()),
+                rate_limit,
+            )
+        };
+
         let server = axum::serve(
             listener,
             Router::new()
                 .route("/", get(serve_index))
                 .route("/signup", post(routes::signup))
-                .route("/login", post(routes::login))
-                .route("/verify-2fa", post(routes::verify_2fa))
+                .route(
+                    "/login",
+                    post(routes::login).layer(rate_limited(rate_limits.login)),
+                )
+                .route(
+                    "/login/token",
+                    post(routes::login_token).layer(rate_limited(rate_limits.login)),
+                )
+                .route("/login/magic-link", post(routes::request_magic_link))
+                .route("/login/magic-link/consume", get(routes::consume_magic_link))
+                .route(
+                    "/verify-2fa",
+                    post(routes::verify_2fa).layer(rate_limited(rate_limits.verify_2fa)),
+                )
+                .route(
+                    "/verify-2fa/token",
+                    post(routes::verify_2fa_token).layer(rate_limited(rate_limits.verify_2fa)),
+                )
+                .route("/2fa/totp/enroll", post(routes::enroll_totp))
+                .route("/2fa/totp/confirm", post(routes::confirm_totp))
+                .route("/2fa/recovery-codes", get(routes::recovery_codes_status))
+                .route(
+                    "/2fa/recovery-codes/regenerate",
+                    post(routes::regenerate_recovery_codes),
+                )
+                .route(
+                    "/passkey/register/start",
+                    post(routes::start_passkey_registration),
+                )
+                .route(
+                    "/passkey/register/finish",
+                    post(routes::finish_passkey_registration),
+                )
+                .route("/passkey/login/start", post(routes::start_passkey_login))
+                .route("/passkey/login/finish", post(routes::finish_passkey_login))
+                .route("/verify-email", get(routes::verify_email))
+                .route(
+                    "/verify-email/resend",
+                    post(routes::resend_verification_email),
+                )
                 .route("/logout", post(routes::logout))
+                .route("/logout-all", post(routes::logout_all))
+                .route("/change-password", post(routes::change_password))
+                .route("/sessions", get(routes::list_sessions))
+                .route("/sessions/:id/revoke", post(routes::revoke_session))
                 .route("/verify-token", post(routes::verify_token))
+                .route("/introspect", post(routes::introspect))
+                .route("/revoke", post(routes::revoke))
+                .route("/token/refresh", post(routes::refresh_token))
+                .route("/token/audience", post(routes::audience_token))
+                .route("/.well-known/jwks.json", get(routes::jwks))
+                .route("/account/export", get(routes::export_account))
+                .route(
+                    "/account/consents",
+                    get(routes::list_consents).post(routes::set_consent),
+                )
+                .route("/account/email", post(routes::change_email))
+                .route("/account/email/confirm", get(routes::confirm_email_change))
+                .route("/account/email/cancel", get(routes::cancel_email_change))
+                .route("/account/delete", post(routes::delete_account))
+                .route(
+                    "/account/delete/cancel",
+                    get(routes::cancel_account_deletion),
+                )
+                .route("/admin/roles", get(routes::list_roles))
+                .route("/admin/users", get(routes::admin_list_users))
+                .route(
+                    "/admin/users/:id",
+                    get(routes::admin_get_user).delete(routes::admin_delete_user),
+                )
+                .route("/admin/users/:id/disable", post(routes::admin_disable_user))
+                .route("/admin/users/:id/enable", post(routes::admin_enable_user))
+                .route(
+                    "/admin/users/:id/force-password-reset",
+                    post(routes::admin_force_password_reset),
+                )
+                .route(
+                    "/admin/users/:id/requires-2fa",
+                    post(routes::admin_set_requires_2fa),
+                )
+                .route("/password-reset", get(serve_password_reset_page))
+                .route(
+                    "/password-reset/request",
+                    post(routes::request_password_reset),
+                )
+                .route(
+                    "/password-reset/confirm",
+                    post(routes::confirm_password_reset),
+                )
                 .nest_service("/assets", ServeDir::new("assets"))
                 .with_state(app_state)
                 .layer(cors)
@@ -71,7 +180,9 @@ impl Application {
                         .make_span_with(make_span_with_request_id)
                         .on_request(on_request)
                         .on_response(on_response),
-                ),
+                )
+                // Sessions record the client address when no proxy supplies one
+                .into_make_service_with_connect_info::<SocketAddr>(),
         );
         Ok(Application { server, address })
     }
@@ -86,6 +197,11 @@ async fn serve_index() -> Html<&'static str> {
     Html(include_str!("../assets/index.html"))
 }
 
+// Where the emailed reset link lands; the page posts the token to /password-reset/confirm
+async fn serve_password_reset_page() -> Html<&'static str> {
+    Html(include_str!("../assets/password_reset.html"))
+}
+
 /// Load allowed origins from env; "*" => wildcard (Any).
 fn load_allowed_origins() -> Result<Option<Vec<HeaderValue>>, Box<dyn Error>> {
     let raw = env::var(ALLOWED_ORIGINS_ENV_VAR)
@@ -2,17 +2,29 @@ use auth_service::{
     app_state::AppState,
     domain::Email,
     get_postgres_pool, get_redis_client,
+    routes::purge_deleted_accounts,
     services::{
-        PostgresUserStore, PostmarkEmailClient, RedisBannedTokenStore, RedisTwoFACodeStore,
+        PostgresAuditEventStore, PostgresConsentStore, PostgresPasskeyStore, PostgresUserStore,
+        PostmarkEmailClient, RedisBannedTokenStore, RedisMagicLinkStore, RedisOneTimeTokenStore,
+        RedisRateLimitStore, RedisRefreshTokenStore, RedisSessionStore, RedisTwoFACodeStore,
+    },
+    utils::{
+        init_tracing, prod, reload_jwt_keyring, DATABASE_URL, POSTMARK_AUTH_TOKEN, REDIS_HOST_NAME,
     },
-    utils::{init_tracing, prod, DATABASE_URL, POSTMARK_AUTH_TOKEN, REDIS_HOST_NAME},
     Application,
 };
 use reqwest::Client;
 use secrecy::Secret;
 use sqlx::PgPool;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::RwLock,
+    time::interval,
+};
+
+// How often accounts past their deletion grace period are looked for
+const ACCOUNT_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[tokio::main]
 async fn main() {
@@ -28,18 +40,39 @@ async fn main() {
             .expect("Failed to get Redis connection"),
     ));
 
-    let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool)));
+    let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool.clone())));
+    let passkey_store = Arc::new(RwLock::new(PostgresPasskeyStore::new(pg_pool.clone())));
+    let audit_event_store = Arc::new(RwLock::new(PostgresAuditEventStore::new(pg_pool.clone())));
+    let consent_store = Arc::new(RwLock::new(PostgresConsentStore::new(pg_pool)));
     let banned_token_store = Arc::new(RwLock::new(RedisBannedTokenStore::new(redis_conn.clone())));
     let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(redis_conn.clone())));
+    let refresh_token_store =
+        Arc::new(RwLock::new(RedisRefreshTokenStore::new(redis_conn.clone())));
+    let magic_link_store = Arc::new(RwLock::new(RedisMagicLinkStore::new(redis_conn.clone())));
+    let session_store = Arc::new(RwLock::new(RedisSessionStore::new(redis_conn.clone())));
+    let one_time_token_store =
+        Arc::new(RwLock::new(RedisOneTimeTokenStore::new(redis_conn.clone())));
+    let rate_limit_store = Arc::new(RwLock::new(RedisRateLimitStore::new(redis_conn.clone())));
     let email_client = Arc::new(configure_postmark_email_client());
 
     let app_state = AppState::new(
         user_store,
         banned_token_store,
         two_fa_code_store,
+        refresh_token_store,
+        one_time_token_store,
+        passkey_store,
+        magic_link_store,
+        session_store,
+        rate_limit_store,
+        audit_event_store,
+        consent_store,
         email_client,
     );
 
+    tokio::spawn(reload_jwt_keyring_on_hangup());
+    tokio::spawn(purge_deleted_accounts_periodically(app_state.clone()));
+
     let app = Application::build(app_state, prod::APP_ADDRESS)
         .await
         .expect("Failed to BUILD auth-service");
@@ -47,6 +80,32 @@ async fn main() {
     app.run().await.expect("Failed to START auth-service");
 }
 
+// Operators rotate JWT keys by updating the key files and sending SIGHUP
+async fn reload_jwt_keyring_on_hangup() {
+    let mut hangup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+    while hangup.recv().await.is_some() {
+        match reload_jwt_keyring() {
+            Ok(()) => tracing::info!("Reloaded JWT keyring"),
+            Err(e) => tracing::error!(
+                "Failed to reload JWT keyring, keeping current keys: {:?}",
+                e
+            ),
+        }
+    }
+}
+
+async fn purge_deleted_accounts_periodically(app_state: AppState) {
+    let mut ticks = interval(ACCOUNT_PURGE_INTERVAL);
+    loop {
+        ticks.tick().await;
+        match purge_deleted_accounts(&app_state).await {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!("Deleted {} accounts past their grace period", purged),
+            Err(e) => tracing::error!("Failed to purge deleted accounts: {:?}", e),
+        }
+    }
+}
+
 async fn configure_postgresql() -> PgPool {
     // Create a new database connection pool
     let pg_pool = get_postgres_pool(DATABASE_URL.clone())
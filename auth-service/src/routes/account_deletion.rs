@@ -0,0 +1,219 @@
+use super::{current_user, end_all_sessions, record_audit_event, token_user};
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuditEventKind, AuthAPIError, ClientInfo, OneTimeToken, OneTimeTokenStoreError,
+        TokenPurpose, UserId, UserStoreError, ACCOUNT_DELETION_GRACE_SECONDS,
+    },
+    utils::{
+        AuthenticatedUser, JWT_COOKIE_NAME, PUBLIC_BASE_URL, REFRESH_TOKEN_COOKIE_NAME,
+        TOKEN_TTL_SECONDS,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use chrono::DateTime;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAccountResponse {
+    pub message: String,
+    /// Unix timestamp after which the account is gone for good
+    #[serde(rename = "deleteAfter")]
+    pub delete_after: i64,
+}
+
+#[derive(Deserialize)]
+pub struct CancelAccountDeletionQuery {
+    pub token: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancelAccountDeletionResponse {
+    pub message: String,
+}
+
+/// Schedule the caller's account for deletion after the grace period and log them out everywhere.
+/// Until then the account can't be logged into, and the emailed link restores it.
+#[tracing::instrument(name = "Delete Account", skip_all)]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    client: ClientInfo,
+    jar: CookieJar,
+    Json(request): Json<DeleteAccountRequest>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let email = current_user(&state, &user_id).await?.email;
+
+    // A stolen session cookie alone must not be enough to delete the account
+    state
+        .user_store
+        .read()
+        .await
+        .validate_user(&user_id, &request.password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::InvalidCredentials | UserStoreError::UserNotFound => {
+                AuthAPIError::IncorrectCredentials
+            }
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let delete_after = state
+        .user_store
+        .write()
+        .await
+        .schedule_deletion(&user_id, ACCOUNT_DELETION_GRACE_SECONDS)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let token = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(TokenPurpose::CancelAccountDeletion, &token, &email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let date = DateTime::from_timestamp(delete_after, 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let subject = "Your account will be deleted";
+    let content = format!(
+        "Your account and all its data will be deleted on {}.\n\
+         If you did not ask for this or changed your mind, keep your account: {}/account/delete/cancel?token={}",
+        date,
+        PUBLIC_BASE_URL.as_str(),
+        token.as_ref().expose_secret()
+    );
+    state
+        .email_client
+        .send_email(&email, subject, &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    end_all_sessions(&state, &user_id).await?;
+    record_audit_event(&state, &user_id, AuditEventKind::DeletionRequested, &client).await;
+
+    let jar = jar
+        .remove(JWT_COOKIE_NAME)
+        .remove(REFRESH_TOKEN_COOKIE_NAME);
+
+    Ok((
+        jar,
+        (
+            StatusCode::ACCEPTED,
+            Json(DeleteAccountResponse {
+                message: "Account scheduled for deletion".to_owned(),
+                delete_after,
+            }),
+        ),
+    ))
+}
+
+/// Served as GET because the link in the deletion email is opened directly.
+#[tracing::instrument(name = "Cancel Account Deletion", skip_all)]
+pub async fn cancel_account_deletion(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Query(query): Query<CancelAccountDeletionQuery>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let token = OneTimeToken::parse(query.token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(TokenPurpose::CancelAccountDeletion, &token)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let user = token_user(&state, &email).await?;
+    state
+        .user_store
+        .write()
+        .await
+        .cancel_deletion(&user.id)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    record_audit_event(&state, &user.id, AuditEventKind::DeletionCancelled, &client).await;
+
+    Ok((
+        StatusCode::OK,
+        Json(CancelAccountDeletionResponse {
+            message: "Account deletion cancelled".to_owned(),
+        }),
+    ))
+}
+
+/// Delete every account whose grace period is over. Meant to run periodically in the background;
+/// returns how many accounts were deleted.
+#[tracing::instrument(name = "Purge Deleted Accounts", skip_all)]
+pub async fn purge_deleted_accounts(state: &AppState) -> Result<usize, AuthAPIError> {
+    let due = state
+        .user_store
+        .read()
+        .await
+        .due_deletions()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let mut purged = 0;
+    for user_id in due {
+        match purge_account(state, &user_id).await {
+            Ok(()) => purged += 1,
+            // The account stays due, so the next run tries again
+            Err(e) => tracing::error!("Failed to purge deleted account: {:?}", e),
+        }
+    }
+    Ok(purged)
+}
+
+/// Delete the user at once, together with what Redis holds on them.
+#[tracing::instrument(name = "Purge Account", skip_all)]
+pub(crate) async fn purge_account(state: &AppState, user_id: &UserId) -> Result<(), AuthAPIError> {
+    end_all_sessions(state, user_id).await?;
+
+    // A 2FA code only exists while a login is half done
+    {
+        let mut two_fa_code_store = state.two_fa_code_store.write().await;
+        if two_fa_code_store.get_code(user_id).await.is_ok() {
+            two_fa_code_store
+                .remove_code(user_id)
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        }
+    }
+
+    // The token version has to outlive the JWTs it rejects, but no longer
+    state
+        .banned_token_store
+        .write()
+        .await
+        .expire_token_version(user_id, TOKEN_TTL_SECONDS as u64)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    match state.user_store.write().await.delete_user(user_id).await {
+        Ok(()) | Err(UserStoreError::UserNotFound) => Ok(()),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
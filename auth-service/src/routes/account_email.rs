@@ -0,0 +1,273 @@
+use super::{current_user, end_all_sessions};
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, OneTimeToken, OneTimeTokenStoreError, TokenPurpose, UserStoreError,
+        EMAIL_CHANGE_REVERT_SECONDS,
+    },
+    utils::{AuthenticatedUser, PUBLIC_BASE_URL},
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct ChangeEmailRequest {
+    #[serde(rename = "newEmail")]
+    pub new_email: Secret<String>,
+    pub password: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangeEmailResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct EmailChangeQuery {
+    pub token: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmEmailChangeResponse {
+    pub message: String,
+    /// The address the account now goes by
+    pub email: String,
+}
+
+/// Start switching the caller to a new address. Nothing changes until the link sent to the new
+/// address is followed; the old address is told and can call the change off until then.
+#[tracing::instrument(name = "Change Email", skip_all)]
+pub async fn change_email(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    Json(request): Json<ChangeEmailRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let email = current_user(&state, &user_id).await?.email;
+    let new_email =
+        Email::parse(request.new_email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    if new_email == email {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    // A stolen session cookie alone must not be enough to take the account elsewhere
+    state
+        .user_store
+        .read()
+        .await
+        .validate_user(&user_id, &request.password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::InvalidCredentials | UserStoreError::UserNotFound => {
+                AuthAPIError::IncorrectCredentials
+            }
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .request_email_change(&user_id, &new_email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let confirm_token = OneTimeToken::default();
+    let cancel_token = OneTimeToken::default();
+    {
+        let mut one_time_token_store = state.one_time_token_store.write().await;
+        one_time_token_store
+            .add_token(TokenPurpose::ConfirmEmailChange, &confirm_token, &new_email)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        one_time_token_store
+            .add_token(TokenPurpose::CancelEmailChange, &cancel_token, &email)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    }
+
+    let content = format!(
+        "Confirm that you want to sign in with this address from now on: {}/account/email/confirm?token={}",
+        PUBLIC_BASE_URL.as_str(),
+        confirm_token.as_ref().expose_secret()
+    );
+    state
+        .email_client
+        .send_email(&new_email, "Confirm your new email address", &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    let content = format!(
+        "Someone asked to move your account to {}.\n\
+         If this wasn't you, stop or undo it and change your password: {}/account/email/cancel?token={}",
+        new_email.as_ref().expose_secret(),
+        PUBLIC_BASE_URL.as_str(),
+        cancel_token.as_ref().expose_secret()
+    );
+    state
+        .email_client
+        .send_email(&email, "Your email address is about to change", &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ChangeEmailResponse {
+            message: "Confirmation sent to the new address".to_owned(),
+        }),
+    ))
+}
+
+/// Served as GET because the link in the confirmation email is opened directly.
+/// Sessions carry on, as they belong to the user rather than to an address. The switch is a
+/// single update of the user's row, as nothing else is keyed by the address.
+#[tracing::instrument(name = "Confirm Email Change", skip_all)]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    Query(query): Query<EmailChangeQuery>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let token = OneTimeToken::parse(query.token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let new_email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(TokenPurpose::ConfirmEmailChange, &token)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    // Cancelled, or superseded by a request for yet another address
+    let (_, previous_email) = state
+        .user_store
+        .write()
+        .await
+        .confirm_email_change(&new_email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    // The switch has happened either way; the link sent with the request still undoes it
+    if let Err(e) = send_revert_link(&state, &previous_email, &new_email).await {
+        tracing::error!(
+            "Failed to tell the old address about the email change: {:?}",
+            e
+        );
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ConfirmEmailChangeResponse {
+            message: "Email changed".to_owned(),
+            email: new_email.as_ref().expose_secret().to_owned(),
+        }),
+    ))
+}
+
+/// Give the address an account just left a link to take the account back.
+async fn send_revert_link(
+    state: &AppState,
+    previous_email: &Email,
+    new_email: &Email,
+) -> Result<(), AuthAPIError> {
+    let token = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(TokenPurpose::CancelEmailChange, &token, previous_email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let content = format!(
+        "Your account now signs in with {}.\n\
+         If this wasn't you, undo it within {} hours and change your password: {}/account/email/cancel?token={}",
+        new_email.as_ref().expose_secret(),
+        EMAIL_CHANGE_REVERT_SECONDS / 3600,
+        PUBLIC_BASE_URL.as_str(),
+        token.as_ref().expose_secret()
+    );
+    state
+        .email_client
+        .send_email(previous_email, "Your email address was changed", &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
+
+/// Served as GET because the link in the notification email is opened directly.
+/// Before the change is confirmed it calls the change off; for `EMAIL_CHANGE_REVERT_SECONDS`
+/// after, it moves the account back to the old address and logs it out everywhere.
+#[tracing::instrument(name = "Cancel Email Change", skip_all)]
+pub async fn cancel_email_change(
+    State(state): State<AppState>,
+    Query(query): Query<EmailChangeQuery>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let token = OneTimeToken::parse(query.token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(TokenPurpose::CancelEmailChange, &token)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    // Until the change is confirmed the account still goes by the old address
+    let mut user_store = state.user_store.write().await;
+    match user_store.get_user_by_email(&email).await {
+        Ok(user) => {
+            user_store
+                .cancel_email_change(&user.id)
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+            drop(user_store);
+
+            // Whoever asked for the change may still be logged in, as after an undo
+            end_all_sessions(&state, &user.id).await?;
+            return Ok((
+                StatusCode::OK,
+                Json(ChangeEmailResponse {
+                    message: "Email change cancelled".to_owned(),
+                }),
+            ));
+        }
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    let user_id = user_store
+        .revert_email_change(&email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    drop(user_store);
+
+    // Whoever made the change may still be logged in
+    end_all_sessions(&state, &user_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ChangeEmailResponse {
+            message: "Email change undone".to_owned(),
+        }),
+    ))
+}
@@ -0,0 +1,58 @@
+use super::current_user;
+use crate::{app_state::AppState, domain::AuthAPIError, utils::AuthenticatedUser};
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountExportResponse {
+    /// Unix timestamp of when the export was taken
+    #[serde(rename = "exportedAt")]
+    pub exported_at: i64,
+    pub email: String,
+    /// One entry per store holding data on the user, keyed by its section name
+    pub data: Map<String, Value>,
+}
+
+/// Everything the service stores about the caller, as a downloadable JSON document.
+/// Each store contributes its own section, so nothing has to be listed here.
+#[tracing::instrument(name = "Export Account", skip_all)]
+pub async fn export_account(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let email = current_user(&state, &user_id).await?.email;
+    let mut data = Map::new();
+    for exporter in state.personal_data_exporters() {
+        let exporter = exporter.read().await;
+        if let Some(section) = exporter
+            .export_personal_data(&user_id)
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?
+        {
+            data.insert(exporter.export_section().to_owned(), section);
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        (
+            [(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"account-export.json\"",
+            )],
+            Json(AccountExportResponse {
+                exported_at: Utc::now().timestamp(),
+                email: email.as_ref().expose_secret().to_owned(),
+                data,
+            }),
+        ),
+    ))
+}
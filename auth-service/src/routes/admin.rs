@@ -0,0 +1,277 @@
+use super::{
+    end_all_sessions, issue_recovery_codes, purge_account, send_password_reset, send_recovery_codes,
+};
+use crate::{
+    app_state::AppState,
+    domain::{AdminRole, AuthAPIError, RoleDefinition, TwoFAMethod, User, UserId, UserStoreError},
+    utils::{AdminCaller, RequireRole},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RolesResponse {
+    pub roles: Vec<RoleDefinition>,
+}
+
+#[derive(Deserialize)]
+pub struct ListUsersQuery {
+    pub search: Option<String>,
+    /// 1-based
+    pub page: Option<u32>,
+    #[serde(rename = "perPage")]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct SetRequires2FARequest {
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+}
+
+/// A user as admins see it; never includes credentials.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUserResponse {
+    pub id: String,
+    pub email: String,
+    #[serde(rename = "emailVerified")]
+    pub email_verified: bool,
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: TwoFAMethod,
+    pub disabled: bool,
+    #[serde(rename = "passwordResetRequired")]
+    pub password_reset_required: bool,
+    #[serde(rename = "pendingDeletion")]
+    pub pending_deletion: bool,
+    /// Only filled in when a single user is fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+}
+
+impl From<User> for AdminUserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id.to_string(),
+            email: user.email.as_ref().expose_secret().to_owned(),
+            email_verified: user.email_verified,
+            requires_2fa: user.requires_2fa,
+            two_fa_method: user.two_fa_method,
+            disabled: user.disabled,
+            password_reset_required: user.password_reset_required,
+            pending_deletion: user.pending_deletion,
+            roles: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUsersResponse {
+    pub users: Vec<AdminUserResponse>,
+    pub page: u32,
+    #[serde(rename = "perPage")]
+    pub per_page: u32,
+    pub total: u64,
+}
+
+#[tracing::instrument(name = "List Roles", skip_all)]
+pub async fn list_roles(
+    State(state): State<AppState>,
+    _: RequireRole<AdminRole>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let roles = state
+        .user_store
+        .read()
+        .await
+        .list_roles()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::OK, Json(RolesResponse { roles })))
+}
+
+#[tracing::instrument(name = "Admin List Users", skip_all)]
+pub async fn admin_list_users(
+    State(state): State<AppState>,
+    _: AdminCaller,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query
+        .per_page
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let offset = (page - 1).saturating_mul(per_page);
+
+    let result = state
+        .user_store
+        .read()
+        .await
+        .list_users(search, per_page, offset)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(AdminUsersResponse {
+            users: result.users.into_iter().map(Into::into).collect(),
+            page,
+            per_page,
+            total: result.total,
+        }),
+    ))
+}
+
+#[tracing::instrument(name = "Admin Get User", skip_all)]
+pub async fn admin_get_user(
+    State(state): State<AppState>,
+    _: AdminCaller,
+    Path(user_id): Path<UserId>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let user = find_user(&state, &user_id).await?;
+    Ok((StatusCode::OK, Json(load_user(&state, &user.id).await?)))
+}
+
+/// Disabling ends every session of the user, so access stops at once rather than when tokens expire.
+#[tracing::instrument(name = "Admin Disable User", skip_all)]
+pub async fn admin_disable_user(
+    State(state): State<AppState>,
+    _: AdminCaller,
+    Path(user_id): Path<UserId>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let user = find_user(&state, &user_id).await?;
+    state
+        .user_store
+        .write()
+        .await
+        .set_disabled(&user.id, true)
+        .await
+        .map_err(user_store_error)?;
+    end_all_sessions(&state, &user.id).await?;
+
+    Ok((StatusCode::OK, Json(load_user(&state, &user.id).await?)))
+}
+
+#[tracing::instrument(name = "Admin Enable User", skip_all)]
+pub async fn admin_enable_user(
+    State(state): State<AppState>,
+    _: AdminCaller,
+    Path(user_id): Path<UserId>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let user = find_user(&state, &user_id).await?;
+    state
+        .user_store
+        .write()
+        .await
+        .set_disabled(&user.id, false)
+        .await
+        .map_err(user_store_error)?;
+
+    Ok((StatusCode::OK, Json(load_user(&state, &user.id).await?)))
+}
+
+/// Refuse the current password from now on: sessions end and the user is emailed a reset link.
+/// Logins without the password, e.g. by passkey or magic link, keep working.
+#[tracing::instrument(name = "Admin Force Password Reset", skip_all)]
+pub async fn admin_force_password_reset(
+    State(state): State<AppState>,
+    _: AdminCaller,
+    Path(user_id): Path<UserId>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let user = find_user(&state, &user_id).await?;
+    state
+        .user_store
+        .write()
+        .await
+        .require_password_reset(&user.id)
+        .await
+        .map_err(user_store_error)?;
+    end_all_sessions(&state, &user.id).await?;
+    send_password_reset(&state, &user.email).await?;
+
+    Ok((StatusCode::OK, Json(load_user(&state, &user.id).await?)))
+}
+
+#[tracing::instrument(name = "Admin Set Requires 2FA", skip_all)]
+pub async fn admin_set_requires_2fa(
+    State(state): State<AppState>,
+    _: AdminCaller,
+    Path(user_id): Path<UserId>,
+    Json(request): Json<SetRequires2FARequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let user = find_user(&state, &user_id).await?;
+    state
+        .user_store
+        .write()
+        .await
+        .set_requires_2fa(&user.id, request.requires_2fa)
+        .await
+        .map_err(user_store_error)?;
+
+    // The user wasn't there to see recovery codes, as they would have at signup
+    if request.requires_2fa && !user.requires_2fa {
+        let codes = issue_recovery_codes(&state, &user.id).await?;
+        if let Err(e) = send_recovery_codes(&state, &user.email, &codes).await {
+            tracing::error!(
+                "Failed to send recovery codes, the user can generate new ones: {:?}",
+                e
+            );
+        }
+    }
+
+    Ok((StatusCode::OK, Json(load_user(&state, &user.id).await?)))
+}
+
+#[tracing::instrument(name = "Admin Delete User", skip_all)]
+pub async fn admin_delete_user(
+    State(state): State<AppState>,
+    _: AdminCaller,
+    Path(user_id): Path<UserId>,
+) -> Result<StatusCode, AuthAPIError> {
+    let user = find_user(&state, &user_id).await?;
+    purge_account(&state, &user.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_user(state: &AppState, user_id: &UserId) -> Result<User, AuthAPIError> {
+    state
+        .user_store
+        .read()
+        .await
+        .get_user(user_id)
+        .await
+        .map_err(user_store_error)
+}
+
+async fn load_user(state: &AppState, user_id: &UserId) -> Result<AdminUserResponse, AuthAPIError> {
+    let store = state.user_store.read().await;
+    let user = store.get_user(user_id).await.map_err(user_store_error)?;
+    let roles = store.get_roles(user_id).await.map_err(user_store_error)?;
+    Ok(AdminUserResponse {
+        roles: Some(roles.roles),
+        ..user.into()
+    })
+}
+
+fn user_store_error(e: UserStoreError) -> AuthAPIError {
+    match e {
+        UserStoreError::UserNotFound => AuthAPIError::UserNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
@@ -0,0 +1,62 @@
+use super::user_roles;
+use crate::{
+    app_state::AppState,
+    domain::AuthAPIError,
+    utils::{issue_audience_token, AuthenticatedUser, TOKEN_TTL_SECONDS},
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct AudienceTokenRequest {
+    pub audience: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudienceTokenResponse {
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "tokenType")]
+    pub token_type: String,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: i64,
+    pub audience: String,
+}
+
+/// Mint an access token that only the given downstream service accepts, so it can't be
+/// replayed against any other. It belongs to the caller's session and is revoked with it.
+/// Tokens for downstream audiences don't authenticate here, so they can't mint further tokens.
+#[tracing::instrument(name = "Audience Token", skip_all)]
+pub async fn audience_token(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, claims }: AuthenticatedUser,
+    Json(request): Json<AudienceTokenRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    if !state.token_audiences.contains(&request.audience) {
+        return Err(AuthAPIError::InvalidAudience);
+    }
+
+    let roles = user_roles(&state, &user_id).await?;
+    let access_token = {
+        let banned_store = state.banned_token_store.read().await;
+        issue_audience_token(
+            &user_id,
+            &claims.sid,
+            &request.audience,
+            &roles,
+            &*banned_store,
+        )
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(AudienceTokenResponse {
+            access_token,
+            token_type: "Bearer".to_owned(),
+            expires_in: TOKEN_TTL_SECONDS,
+            audience: request.audience,
+        }),
+    ))
+}
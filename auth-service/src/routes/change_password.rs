@@ -0,0 +1,90 @@
+use super::{record_audit_event, user_roles};
+use crate::{
+    app_state::AppState,
+    domain::{AuditEventKind, AuthAPIError, ClientInfo, Password, UserStoreError},
+    utils::{generate_auth_cookie, AuthenticatedUser},
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::Secret;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: Secret<String>,
+    #[serde(rename = "newPassword")]
+    pub new_password: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangePasswordResponse {
+    pub message: String,
+}
+
+#[tracing::instrument(name = "Change Password", skip_all)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, claims }: AuthenticatedUser,
+    client: ClientInfo,
+    jar: CookieJar,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let new_password =
+        Password::parse(request.new_password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    // A stolen session cookie alone must not be enough to take over the account
+    state
+        .user_store
+        .read()
+        .await
+        .validate_user(&user_id, &request.current_password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::InvalidCredentials | UserStoreError::UserNotFound => {
+                AuthAPIError::IncorrectCredentials
+            }
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(&user_id, new_password)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    // Keep the caller's own session, whose id is its refresh token family's; end every other one
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_other_families(&user_id, &claims.sid)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    // Their JWTs go too; the caller gets a fresh one carrying the new token version
+    let roles = user_roles(&state, &user_id).await?;
+    let auth_cookie = {
+        let mut banned_store = state.banned_token_store.write().await;
+        banned_store
+            .increment_token_version(&user_id)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        generate_auth_cookie(&user_id, &claims.sid, &roles, &*banned_store)
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?
+    };
+    record_audit_event(&state, &user_id, AuditEventKind::PasswordChanged, &client).await;
+
+    Ok((
+        jar.add(auth_cookie),
+        (
+            StatusCode::OK,
+            Json(ChangePasswordResponse {
+                message: "Password changed".to_owned(),
+            }),
+        ),
+    ))
+}
@@ -0,0 +1,62 @@
+use super::record_audit_event;
+use crate::{
+    app_state::AppState,
+    domain::{AuditEventKind, AuthAPIError, ClientInfo, Consent, ConsentPurpose},
+    utils::AuthenticatedUser,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct SetConsentRequest {
+    pub purpose: String,
+    pub granted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsentsResponse {
+    pub consents: Vec<Consent>,
+}
+
+#[tracing::instrument(name = "List Consents", skip_all)]
+pub async fn list_consents(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let consents = state
+        .consent_store
+        .read()
+        .await
+        .get_consents(&user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::OK, Json(ConsentsResponse { consents })))
+}
+
+/// Record the caller's answer for one purpose; only the latest answer per purpose is kept.
+#[tracing::instrument(name = "Set Consent", skip_all)]
+pub async fn set_consent(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    client: ClientInfo,
+    Json(request): Json<SetConsentRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let purpose =
+        ConsentPurpose::parse(&request.purpose).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let consents = {
+        let mut consent_store = state.consent_store.write().await;
+        consent_store
+            .set_consent(&user_id, &purpose, request.granted)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        consent_store
+            .get_consents(&user_id)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+    };
+    record_audit_event(&state, &user_id, AuditEventKind::ConsentChanged, &client).await;
+
+    Ok((StatusCode::OK, Json(ConsentsResponse { consents })))
+}
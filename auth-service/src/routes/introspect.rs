@@ -0,0 +1,67 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, AuthenticatedClient},
+    utils::{validate_token_for, JWT_AUDIENCE},
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Form, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct IntrospectRequest {
+    pub token: String,
+    /// Accepted as RFC 7662 asks, but only access tokens can be introspected
+    pub token_type_hint: Option<String>,
+}
+
+/// RFC 7662 introspection response. An inactive token gets `active: false` and nothing else.
+/// `client_id` is left out: tokens don't record the client they were issued to, `aud` names
+/// the service they are for.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntrospectResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+}
+
+/// Tell a resource server whether an access token is currently valid, and for whom.
+/// Banned, revoked and logged-out-everywhere tokens are reported inactive, exactly as `validate_token` sees them.
+/// Besides this service's own tokens, a client may only introspect tokens minted for it.
+#[tracing::instrument(name = "Introspect Token", skip_all, fields(client_id = %client.0.id))]
+pub async fn introspect(
+    State(state): State<AppState>,
+    client: AuthenticatedClient,
+    Form(request): Form<IntrospectRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let audiences = [JWT_AUDIENCE.as_str(), client.0.id.as_str()];
+    let banned_store = state.banned_token_store.read().await;
+    let response = match validate_token_for(&request.token, &audiences, &*banned_store).await {
+        Ok(claims) => IntrospectResponse {
+            active: true,
+            sub: Some(claims.sub),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            nbf: Some(claims.nbf),
+            iss: Some(claims.iss),
+            aud: Some(claims.aud),
+            scope: Some(claims.scope).filter(|scope| !scope.is_empty()),
+            token_type: Some("Bearer".to_owned()),
+        },
+        Err(_) => IntrospectResponse::default(),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
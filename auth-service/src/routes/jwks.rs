@@ -0,0 +1,9 @@
+use crate::utils::jwt_keyring;
+use axum::Json;
+use jsonwebtoken::jwk::JwkSet;
+
+/// Publish the current and previous signing keys so other services can verify tokens themselves.
+#[tracing::instrument(name = "JWKS", skip_all)]
+pub async fn jwks() -> Json<JwkSet> {
+    Json(jwt_keyring().jwks())
+}
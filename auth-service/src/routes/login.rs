@@ -1,11 +1,15 @@
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, LoginAttemptId, TwoFACode},
-    utils::generate_auth_cookie,
+    domain::{
+        AuditEventKind, AuthAPIError, ClientInfo, Email, LoginAttemptId, RefreshToken,
+        RefreshTokenFamily, Session, TwoFACode, TwoFAMethod, UnverifiedLoginPolicy, User, UserId,
+        UserRoles, UserStoreError,
+    },
+    utils::{create_auth_cookie, create_refresh_cookie, issue_auth_token, TOKEN_TTL_SECONDS},
 };
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::CookieJar;
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
@@ -19,6 +23,7 @@ pub struct LoginRequest {
 pub enum LoginResponse {
     RegularAuth,
     TwoFactorAuth(TwoFactorAuthResponse),
+    Token(TokenResponse),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,33 +31,104 @@ pub struct TwoFactorAuthResponse {
     pub message: String,
     #[serde(rename = "loginAttemptId")]
     pub login_attempt_id: String,
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: TwoFAMethod,
+}
+
+/// Tokens handed to API clients in the body, for callers that can't keep cookies.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenResponse {
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "tokenType")]
+    pub token_type: String,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: i64,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+}
+
+impl From<SessionTokens> for TokenResponse {
+    fn from(tokens: SessionTokens) -> Self {
+        Self {
+            access_token: tokens.access_token,
+            token_type: "Bearer".to_owned(),
+            expires_in: TOKEN_TTL_SECONDS,
+            refresh_token: tokens.refresh_token.as_ref().expose_secret().to_owned(),
+        }
+    }
 }
 
 #[tracing::instrument(name = "Login", skip_all)]
 pub async fn login(
     State(state): State<AppState>,
+    client: ClientInfo,
     jar: CookieJar,
     Json(request): Json<LoginRequest>,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let user = authenticate_login(&state, &request).await?;
+
+    let (jar, resp) = match user.requires_2fa {
+        true => handle_2fa(&user, &state, jar).await,
+        false => handle_no_2fa(&user.id, &client, &state, jar).await,
+    };
+    let resp = resp?; // propagate error if any
+    Ok((jar, resp.into_response()))
+}
+
+/// Same as `login`, but a session is returned as tokens in the body instead of cookies.
+#[tracing::instrument(name = "Login For Token", skip_all)]
+pub async fn login_token(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(request): Json<LoginRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let user = authenticate_login(&state, &request).await?;
+
+    if user.requires_2fa {
+        // The login attempt id is in the body, the jar is left untouched
+        let (_, resp) = handle_2fa(&user, &state, CookieJar::new()).await;
+        return resp;
+    }
+
+    let tokens = issue_session(&user.id, &client, &state).await?;
+    Ok((StatusCode::OK, Json(LoginResponse::Token(tokens.into()))))
+}
+
+#[tracing::instrument(name = "Authenticate Login", skip_all)]
+async fn authenticate_login(
+    state: &AppState,
+    request: &LoginRequest,
+) -> Result<User, AuthAPIError> {
+    let email = Email::parse(Secret::new(request.email.clone()))
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
     let user = state
         .user_store
         .read()
         .await
-        .authenticate_user(&request.email, &request.password)
+        .authenticate_user(&email, &request.password)
         .await
         .map_err(|_| AuthAPIError::IncorrectCredentials)?;
 
-    let (jar, resp) = match user.requires_2fa {
-        true => handle_2fa(&user.email, &state, jar).await,
-        false => handle_no_2fa(&user.email, jar).await,
-    };
-    let resp = resp?; // propagate error if any
-    Ok((jar, resp.into_response()))
+    // Checked after the password so the answers reveal nothing to someone without it
+    if user.disabled {
+        return Err(AuthAPIError::AccountDisabled);
+    }
+    if user.pending_deletion {
+        return Err(AuthAPIError::AccountPendingDeletion);
+    }
+    if !user.email_verified && state.unverified_login_policy == UnverifiedLoginPolicy::Deny {
+        return Err(AuthAPIError::EmailNotVerified);
+    }
+    if user.password_reset_required {
+        return Err(AuthAPIError::PasswordResetRequired);
+    }
+    Ok(user)
 }
 
 #[tracing::instrument(name = "Handle 2FA", skip_all)]
-async fn handle_2fa(
-    email: &Email,
+pub(crate) async fn handle_2fa(
+    user: &User,
     state: &AppState,
     jar: CookieJar,
 ) -> (
@@ -60,52 +136,200 @@ async fn handle_2fa(
     Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
 ) {
     let login_attempt_id = LoginAttemptId::default();
+    // With TOTP the code comes from the user's app; the stored one is never sent and only the
+    // login attempt id matters, binding verify_2fa to a login that passed the password check.
     let two_fa_code = TwoFACode::default();
 
     let add_result = state
         .two_fa_code_store
         .write()
         .await
-        .add_code(email.clone(), login_attempt_id.clone(), two_fa_code.clone())
+        .add_code(user.id, login_attempt_id.clone(), two_fa_code.clone())
         .await;
     if let Err(e) = add_result {
         return (jar, Err(AuthAPIError::UnexpectedError(e.into())));
     }
 
-    // Send the 2FA code via email
-    let subject = "Your 2FA Code";
-    let content = format!("Your 2FA code is: {}", two_fa_code.as_ref());
-    if let Err(e) = state
-        .email_client
-        .send_email(email, subject, &content)
-        .await
-    {
-        return (jar, Err(AuthAPIError::UnexpectedError(e)));
+    if user.two_fa_method == TwoFAMethod::Email {
+        // Send the 2FA code via email
+        let subject = "Your 2FA Code";
+        let content = format!("Your 2FA code is: {}", two_fa_code.as_ref());
+        if let Err(e) = state
+            .email_client
+            .send_email(&user.email, subject, &content)
+            .await
+        {
+            return (jar, Err(AuthAPIError::UnexpectedError(e)));
+        }
     }
 
     let response = Json(LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
         message: "2FA required".to_owned(),
         login_attempt_id: login_attempt_id.as_ref().to_owned(),
+        two_fa_method: user.two_fa_method,
     }));
 
     (jar, Ok((StatusCode::PARTIAL_CONTENT, response)))
 }
 
 #[tracing::instrument(name = "Handle non-2FA", skip_all)]
-async fn handle_no_2fa(
-    email: &Email,
+pub(crate) async fn handle_no_2fa(
+    user_id: &UserId,
+    client: &ClientInfo,
+    state: &AppState,
     jar: CookieJar,
 ) -> (
     CookieJar,
     Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
 ) {
-    let auth_cookie = match generate_auth_cookie(email) {
-        Ok(cookie) => cookie,
-        Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
+    match start_session(user_id, client, state, jar.clone()).await {
+        Ok(updated_jar) => (
+            updated_jar,
+            Ok((StatusCode::OK, Json(LoginResponse::RegularAuth))),
+        ),
+        Err(e) => (jar, Err(e)),
+    }
+}
+
+/// Tokens of a freshly started session.
+pub(crate) struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: RefreshToken,
+}
+
+/// Start a session for the user and add its auth and refresh cookies to the jar.
+#[tracing::instrument(name = "Start Session", skip_all)]
+pub(crate) async fn start_session(
+    user_id: &UserId,
+    client: &ClientInfo,
+    state: &AppState,
+    jar: CookieJar,
+) -> Result<CookieJar, AuthAPIError> {
+    let tokens = issue_session(user_id, client, state).await?;
+    Ok(jar
+        .add(create_auth_cookie(tokens.access_token))
+        .add(create_refresh_cookie(&tokens.refresh_token)))
+}
+
+/// Start a session for the user and issue its first auth and refresh tokens.
+/// The refresh token family's id doubles as the session id carried by every JWT of the session.
+#[tracing::instrument(name = "Issue Session", skip_all)]
+pub(crate) async fn issue_session(
+    user_id: &UserId,
+    client: &ClientInfo,
+    state: &AppState,
+) -> Result<SessionTokens, AuthAPIError> {
+    // Every login flow ends here, so none of them lets a disabled or deleted account in
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    ensure_account_active(&user)?;
+
+    let family = RefreshTokenFamily::new(*user_id);
+    let roles = user_roles(state, user_id).await?;
+    let access_token = {
+        let banned_store = state.banned_token_store.read().await;
+        issue_auth_token(user_id, &family.id, &roles, &*banned_store)
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?
     };
-    let updated_jar = jar.add(auth_cookie);
-    (
-        updated_jar,
-        Ok((StatusCode::OK, Json(LoginResponse::RegularAuth))),
-    )
+    let refresh_token = RefreshToken::default();
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .add_token(&refresh_token, &family)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .session_store
+        .write()
+        .await
+        .add_session(Session::new(family.id.clone(), *user_id, client))
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    record_audit_event(state, user_id, AuditEventKind::Login, client).await;
+
+    Ok(SessionTokens {
+        access_token,
+        refresh_token,
+    })
+}
+
+/// Refuse accounts that may not hold a session, at login and at every refresh.
+pub(crate) fn ensure_account_active(user: &User) -> Result<(), AuthAPIError> {
+    if user.disabled {
+        return Err(AuthAPIError::AccountDisabled);
+    }
+    if user.pending_deletion {
+        return Err(AuthAPIError::AccountPendingDeletion);
+    }
+    Ok(())
+}
+
+/// Note in the user's audit log that `kind` happened. The action itself already succeeded,
+/// so failing to record it is only logged.
+#[tracing::instrument(name = "Record Audit Event", skip_all)]
+pub(crate) async fn record_audit_event(
+    state: &AppState,
+    user_id: &UserId,
+    kind: AuditEventKind,
+    client: &ClientInfo,
+) {
+    if let Err(e) = state
+        .audit_event_store
+        .write()
+        .await
+        .record_event(user_id, kind, client)
+        .await
+    {
+        tracing::error!("Failed to record {} audit event: {:?}", kind.as_str(), e);
+    }
+}
+
+/// The user a valid token was issued to. Gone users are treated like an invalid token.
+pub(crate) async fn current_user(state: &AppState, user_id: &UserId) -> Result<User, AuthAPIError> {
+    state
+        .user_store
+        .read()
+        .await
+        .get_user(user_id)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })
+}
+
+/// The user an emailed token was sent to. Once they left that address the token is invalid.
+pub(crate) async fn token_user(state: &AppState, email: &Email) -> Result<User, AuthAPIError> {
+    state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })
+}
+
+/// The user's current roles, to be embedded in a token about to be issued.
+pub(crate) async fn user_roles(
+    state: &AppState,
+    user_id: &UserId,
+) -> Result<UserRoles, AuthAPIError> {
+    state
+        .user_store
+        .read()
+        .await
+        .get_roles(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
 }
@@ -1,35 +1,138 @@
+use super::record_audit_event;
 use crate::{
     app_state::AppState,
-    domain::AuthAPIError,
-    utils::{decode_claims, JWT_COOKIE_NAME},
+    domain::{
+        AuditEventKind, AuthAPIError, ClientInfo, RefreshToken, RefreshTokenStoreError,
+        SessionStoreError, UserId,
+    },
+    utils::{
+        decode_claims, latest_token_expiry, request_token, AuthenticatedUser, JWT_COOKIE_NAME,
+        REFRESH_TOKEN_COOKIE_NAME,
+    },
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
+use secrecy::Secret;
 
 #[tracing::instrument(name = "Logout", skip_all)]
 pub async fn logout(
     State(app_state): State<AppState>,
+    client: ClientInfo,
+    headers: HeaderMap,
     jar: CookieJar,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
-    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
-    let token = cookie.value();
+    // Bearer clients log out with the token they were given, browsers with the cookie
+    let token = request_token(&headers).ok_or(AuthAPIError::MissingToken)?;
 
     // Decode outside of any lock
-    decode_claims(token).map_err(|_| AuthAPIError::InvalidToken)?;
+    let claims = decode_claims(&token).map_err(|_| AuthAPIError::InvalidToken)?;
 
-    // Atomic check+insert under one write lock
-    let newly_banned = {
+    // Atomic check+insert under one write lock. A token that is already banned is no error,
+    // so logging out twice succeeds too.
+    {
         let mut store = app_state.banned_token_store.write().await;
         store
-            .ban_if_not_present(token)
+            .ban_if_not_present(&claims.jti, claims.exp)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        // Other tokens issued to the session, e.g. before a refresh, end with it
+        store
+            .add_banned_token(&claims.sid, latest_token_expiry())
             .await
-            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
-    };
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    }
+
+    match app_state
+        .session_store
+        .write()
+        .await
+        .remove_session(&claims.sid)
+        .await
+    {
+        Ok(()) | Err(SessionStoreError::SessionNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
 
-    if !newly_banned {
-        // Token already banned: idempotent success, better UX
-        return Ok((jar.remove(JWT_COOKIE_NAME), StatusCode::OK));
+    // End the refresh token family too, otherwise the session could simply be refreshed
+    if let Some(refresh_token) = jar
+        .get(REFRESH_TOKEN_COOKIE_NAME)
+        .and_then(|cookie| RefreshToken::parse(Secret::new(cookie.value().to_owned())).ok())
+    {
+        let mut refresh_store = app_state.refresh_token_store.write().await;
+        match refresh_store.get_family(&refresh_token).await {
+            Ok(family) => refresh_store
+                .revoke_family(&family.id)
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?,
+            Err(RefreshTokenStoreError::TokenNotFound) => {}
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
     }
 
-    Ok((jar.remove(JWT_COOKIE_NAME), StatusCode::OK))
+    if let Ok(user_id) = UserId::parse(&claims.sub) {
+        record_audit_event(&app_state, &user_id, AuditEventKind::Logout, &client).await;
+    }
+
+    let jar = jar
+        .remove(JWT_COOKIE_NAME)
+        .remove(REFRESH_TOKEN_COOKIE_NAME);
+
+    Ok((jar, StatusCode::OK))
+}
+
+/// Log the user out of every session, including the one making the request.
+#[tracing::instrument(name = "Logout All", skip_all)]
+pub async fn logout_all(
+    State(app_state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    client: ClientInfo,
+    jar: CookieJar,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    end_all_sessions(&app_state, &user_id).await?;
+    record_audit_event(&app_state, &user_id, AuditEventKind::LogoutAll, &client).await;
+
+    let jar = jar
+        .remove(JWT_COOKIE_NAME)
+        .remove(REFRESH_TOKEN_COOKIE_NAME);
+
+    Ok((jar, StatusCode::OK))
+}
+
+/// Invalidate every token issued to the user: JWTs by moving to a new token version,
+/// refresh tokens by revoking their families. Returns the new token version.
+/// For anything that must cut off all access at once, e.g. a password reset or a compromised account.
+#[tracing::instrument(name = "End All Sessions", skip_all)]
+pub(crate) async fn end_all_sessions(
+    state: &AppState,
+    user_id: &UserId,
+) -> Result<u64, AuthAPIError> {
+    let token_version = state
+        .banned_token_store
+        .write()
+        .await
+        .increment_token_version(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_all_families(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .session_store
+        .write()
+        .await
+        .remove_all_sessions(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(token_version)
 }
@@ -0,0 +1,152 @@
+use super::{handle_2fa, handle_no_2fa};
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, ClientInfo, Email, MagicLinkStoreError, OneTimeToken, UserStoreError},
+    utils::{
+        create_magic_link_nonce_cookie, sign_magic_link_token, verify_magic_link_token,
+        MAGIC_LINK_NONCE_COOKIE_NAME, MAGIC_LINK_TTL_SECONDS, PUBLIC_BASE_URL,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MagicLinkResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct MagicLinkQuery {
+    pub token: Secret<String>,
+}
+
+/// Email a single-use login link. The browser asking for it gets a nonce cookie,
+/// and only that browser can redeem the link.
+#[tracing::instrument(name = "Request Magic Link", skip_all)]
+pub async fn request_magic_link(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Json(request): Json<MagicLinkRequest>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    // Same answer, cookie included, whether or not the account exists
+    let nonce = OneTimeToken::default();
+    let jar = jar.add(create_magic_link_nonce_cookie(&nonce));
+    let response = (
+        StatusCode::OK,
+        Json(MagicLinkResponse {
+            message: "If the account exists, a login link has been sent".to_owned(),
+        }),
+    );
+
+    let Ok(email) = Email::parse(request.email) else {
+        return Ok((jar, response));
+    };
+
+    match state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(&email)
+        .await
+    {
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return Ok((jar, response)),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    let token = OneTimeToken::default();
+    state
+        .magic_link_store
+        .write()
+        .await
+        .add_link(&token, &email, &nonce)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let signed = sign_magic_link_token(&token).map_err(AuthAPIError::UnexpectedError)?;
+    let subject = "Your login link";
+    let content = format!(
+        "Use this link to log in: {}/login/magic-link/consume?token={}\n\
+         Open it in the browser you requested it from. It expires in {} minutes.\n\
+         If you did not try to log in, ignore this email.",
+        PUBLIC_BASE_URL.as_str(),
+        signed,
+        MAGIC_LINK_TTL_SECONDS / 60
+    );
+    state
+        .email_client
+        .send_email(&email, subject, &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok((jar, response))
+}
+
+/// Served as GET because the link in the email is opened directly.
+/// The link stands in for the password; users with 2FA still have to provide their code.
+#[tracing::instrument(name = "Consume Magic Link", skip_all)]
+pub async fn consume_magic_link(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    jar: CookieJar,
+    Query(query): Query<MagicLinkQuery>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let token = verify_magic_link_token(query.token.expose_secret())
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    let nonce = jar
+        .get(MAGIC_LINK_NONCE_COOKIE_NAME)
+        .and_then(|cookie| OneTimeToken::parse(Secret::new(cookie.value().to_owned())).ok())
+        .ok_or(AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .magic_link_store
+        .write()
+        .await
+        .consume_link(&token, &nonce)
+        .await
+        .map_err(|e| match e {
+            MagicLinkStoreError::LinkNotFound | MagicLinkStoreError::NonceMismatch => {
+                AuthAPIError::InvalidToken
+            }
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(&email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    // Following the emailed link proves the address belongs to the user
+    state
+        .user_store
+        .write()
+        .await
+        .mark_email_verified(&user.id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let jar = jar.remove(create_magic_link_nonce_cookie(&nonce));
+    let (jar, resp) = match user.requires_2fa {
+        true => handle_2fa(&user, &state, jar).await,
+        false => handle_no_2fa(&user.id, &client, &state, jar).await,
+    };
+    Ok((jar, resp?.into_response()))
+}
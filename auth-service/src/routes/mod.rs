@@ -1,12 +1,48 @@
+mod account_deletion;
+mod account_email;
+mod account_export;
+mod admin;
+mod audience_token;
+mod change_password;
+mod consents;
+mod introspect;
+mod jwks;
 mod login;
 mod logout;
+mod magic_link;
+mod passkey;
+mod password_reset;
+mod recovery_codes;
+mod refresh_token;
+mod revoke;
+mod sessions;
 mod signup;
+mod totp;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
 
 // re-export items from sub-modules
+pub use account_deletion::*;
+pub use account_email::*;
+pub use account_export::*;
+pub use admin::*;
+pub use audience_token::*;
+pub use change_password::*;
+pub use consents::*;
+pub use introspect::*;
+pub use jwks::*;
 pub use login::*;
 pub use logout::*;
+pub use magic_link::*;
+pub use passkey::*;
+pub use password_reset::*;
+pub use recovery_codes::*;
+pub use refresh_token::*;
+pub use revoke::*;
+pub use sessions::*;
 pub use signup::*;
+pub use totp::*;
 pub use verify_2fa::*;
+pub use verify_email::*;
 pub use verify_token::*;
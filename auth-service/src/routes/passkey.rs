@@ -0,0 +1,422 @@
+use super::{current_user, handle_no_2fa};
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, ClientInfo, Email, OneTimeToken, OneTimeTokenStoreError, PasskeyCredential,
+        PasskeyStoreError, TokenPurpose, UnverifiedLoginPolicy, UserId, UserStoreError,
+        COSE_ALG_EDDSA, COSE_ALG_ES256,
+    },
+    utils::{
+        decode_base64url, parse_attestation_object, parse_authenticator_data, verify_client_data,
+        AuthenticatedUser, CeremonyType, WEBAUTHN_ORIGIN, WEBAUTHN_RP_ID,
+    },
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_extra::extract::CookieJar;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+const RP_NAME: &str = "Auth Service";
+// Milliseconds; matches the lifetime of the stored challenge
+const CEREMONY_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+/// Options for `navigator.credentials.create()`, in the JSON form browsers accept.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyRegistrationOptions {
+    #[serde(rename = "publicKey")]
+    pub public_key: PublicKeyCredentialCreationOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyCredentialCreationOptions {
+    pub challenge: String,
+    pub rp: RelyingParty,
+    pub user: PasskeyUser,
+    pub pub_key_cred_params: Vec<PublicKeyCredentialParameters>,
+    pub exclude_credentials: Vec<PublicKeyCredentialDescriptor>,
+    pub authenticator_selection: AuthenticatorSelection,
+    pub attestation: String,
+    pub timeout: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyUser {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicKeyCredentialParameters {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub alg: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicKeyCredentialDescriptor {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+    pub resident_key: String,
+    pub user_verification: String,
+}
+
+/// Options for `navigator.credentials.get()`, in the JSON form browsers accept.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyLoginOptions {
+    #[serde(rename = "publicKey")]
+    pub public_key: PublicKeyCredentialRequestOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyCredentialRequestOptions {
+    pub challenge: String,
+    pub rp_id: String,
+    pub allow_credentials: Vec<PublicKeyCredentialDescriptor>,
+    pub user_verification: String,
+    pub timeout: u64,
+}
+
+#[derive(Deserialize)]
+pub struct PasskeyLoginStartRequest {
+    pub email: Secret<String>,
+}
+
+/// A `PublicKeyCredential` from `navigator.credentials.create()`, binary fields base64url encoded.
+#[derive(Deserialize)]
+pub struct PasskeyRegistrationRequest {
+    pub id: String,
+    pub response: AuthenticatorAttestationResponse,
+}
+
+#[derive(Deserialize)]
+pub struct AuthenticatorAttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+/// A `PublicKeyCredential` from `navigator.credentials.get()`, binary fields base64url encoded.
+#[derive(Deserialize)]
+pub struct PasskeyLoginRequest {
+    pub id: String,
+    pub response: AuthenticatorAssertionResponse,
+}
+
+#[derive(Deserialize)]
+pub struct AuthenticatorAssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyRegistrationResponse {
+    pub message: String,
+}
+
+/// Begin adding a passkey to the signed-in account.
+#[tracing::instrument(name = "Start Passkey Registration", skip_all)]
+pub async fn start_passkey_registration(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let email = current_user(&state, &user_id).await?.email;
+    let challenge = issue_challenge(&state, TokenPurpose::PasskeyRegistration, &email).await?;
+    // Don't let the same authenticator register twice
+    let exclude_credentials = credential_descriptors(&state, &user_id).await?;
+
+    let options = PublicKeyCredentialCreationOptions {
+        challenge,
+        rp: RelyingParty {
+            id: WEBAUTHN_RP_ID.to_owned(),
+            name: RP_NAME.to_owned(),
+        },
+        user: PasskeyUser {
+            // The user handle must not be personal data, which the user id isn't
+            id: URL_SAFE_NO_PAD.encode(user_id.as_ref().as_bytes()),
+            name: email.as_ref().expose_secret().to_owned(),
+            display_name: email.as_ref().expose_secret().to_owned(),
+        },
+        pub_key_cred_params: [COSE_ALG_ES256, COSE_ALG_EDDSA]
+            .into_iter()
+            .map(|alg| PublicKeyCredentialParameters {
+                credential_type: "public-key".to_owned(),
+                alg,
+            })
+            .collect(),
+        exclude_credentials,
+        authenticator_selection: AuthenticatorSelection {
+            resident_key: "preferred".to_owned(),
+            user_verification: "required".to_owned(),
+        },
+        attestation: "none".to_owned(),
+        timeout: CEREMONY_TIMEOUT_MS,
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(PasskeyRegistrationOptions {
+            public_key: options,
+        }),
+    ))
+}
+
+/// Verify the authenticator's response to a registration challenge and store the new passkey.
+#[tracing::instrument(name = "Finish Passkey Registration", skip_all)]
+pub async fn finish_passkey_registration(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    Json(request): Json<PasskeyRegistrationRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let email = current_user(&state, &user_id).await?.email;
+    let credential_id =
+        decode_base64url(&request.id).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let client_data_json = decode_base64url(&request.response.client_data_json)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let attestation_object = decode_base64url(&request.response.attestation_object)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let client_data = verify_client_data(&client_data_json, CeremonyType::Create, &WEBAUTHN_ORIGIN)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let challenge_email = consume_challenge(
+        &state,
+        TokenPurpose::PasskeyRegistration,
+        &client_data.challenge,
+    )
+    .await?;
+    if challenge_email != email {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    let auth_data = parse_attestation_object(&attestation_object, &WEBAUTHN_RP_ID)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let attested = auth_data
+        .attested_credential
+        .filter(|attested| attested.id == credential_id)
+        .ok_or(AuthAPIError::InvalidCredentials)?;
+    if !auth_data.user_verified {
+        return Err(AuthAPIError::InvalidCredentials);
+    }
+
+    let credential = PasskeyCredential {
+        id: attested.id,
+        user_id,
+        public_key: attested.public_key,
+        sign_count: auth_data.sign_count,
+    };
+    state
+        .passkey_store
+        .write()
+        .await
+        .add_credential(credential)
+        .await
+        .map_err(|e| match e {
+            PasskeyStoreError::CredentialAlreadyExists => AuthAPIError::InvalidCredentials,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(PasskeyRegistrationResponse {
+            message: "Passkey registered".to_owned(),
+        }),
+    ))
+}
+
+/// Begin a passkey login. The answer is the same whether or not the account exists,
+/// apart from the list of its credentials.
+#[tracing::instrument(name = "Start Passkey Login", skip_all)]
+pub async fn start_passkey_login(
+    State(state): State<AppState>,
+    Json(request): Json<PasskeyLoginStartRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let challenge = issue_challenge(&state, TokenPurpose::PasskeyAuthentication, &email).await?;
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(&email)
+        .await;
+    let allow_credentials = match user {
+        Ok(user) => credential_descriptors(&state, &user.id).await?,
+        Err(UserStoreError::UserNotFound) => Vec::new(),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(PasskeyLoginOptions {
+            public_key: PublicKeyCredentialRequestOptions {
+                challenge,
+                rp_id: WEBAUTHN_RP_ID.to_owned(),
+                allow_credentials,
+                user_verification: "required".to_owned(),
+                timeout: CEREMONY_TIMEOUT_MS,
+            },
+        }),
+    ))
+}
+
+/// Verify a passkey assertion and sign the user in. A user-verified passkey proves both
+/// possession and a PIN or biometric, so it stands in for the password and the second factor.
+#[tracing::instrument(name = "Finish Passkey Login", skip_all)]
+pub async fn finish_passkey_login(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    jar: CookieJar,
+    Json(request): Json<PasskeyLoginRequest>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let credential_id =
+        decode_base64url(&request.id).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let client_data_json = decode_base64url(&request.response.client_data_json)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let authenticator_data = decode_base64url(&request.response.authenticator_data)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let signature = decode_base64url(&request.response.signature)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let client_data = verify_client_data(&client_data_json, CeremonyType::Get, &WEBAUTHN_ORIGIN)
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+    let email = consume_challenge(
+        &state,
+        TokenPurpose::PasskeyAuthentication,
+        &client_data.challenge,
+    )
+    .await?;
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(&email)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::IncorrectCredentials,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let credential = state
+        .passkey_store
+        .read()
+        .await
+        .get_credential(&credential_id)
+        .await
+        .map_err(|e| match e {
+            PasskeyStoreError::CredentialNotFound => AuthAPIError::IncorrectCredentials,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    if credential.user_id != user.id {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    let auth_data = parse_authenticator_data(&authenticator_data, &WEBAUTHN_RP_ID)
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+    if !auth_data.user_verified {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+    let signed = [authenticator_data.as_slice(), &client_data.hash].concat();
+    credential
+        .public_key
+        .verify(&signed, &signature)
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+
+    // A counter that fails to advance suggests a cloned authenticator.
+    // Authenticators that don't keep a counter always report zero.
+    if (auth_data.sign_count != 0 || credential.sign_count != 0)
+        && auth_data.sign_count <= credential.sign_count
+    {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+    state
+        .passkey_store
+        .write()
+        .await
+        .update_sign_count(&credential.id, auth_data.sign_count)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    if !user.email_verified && state.unverified_login_policy == UnverifiedLoginPolicy::Deny {
+        return Err(AuthAPIError::EmailNotVerified);
+    }
+
+    let (jar, resp) = handle_no_2fa(&user.id, &client, &state, jar).await;
+    Ok((jar, resp?.into_response()))
+}
+
+/// Store a fresh challenge for `email` and return it base64url encoded, as sent to the browser.
+async fn issue_challenge(
+    state: &AppState,
+    purpose: TokenPurpose,
+    email: &Email,
+) -> Result<String, AuthAPIError> {
+    let challenge = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(purpose, &challenge, email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(URL_SAFE_NO_PAD.encode(challenge.as_ref().expose_secret()))
+}
+
+/// Redeem a challenge echoed back by the authenticator; each one answers a single ceremony.
+async fn consume_challenge(
+    state: &AppState,
+    purpose: TokenPurpose,
+    challenge: &OneTimeToken,
+) -> Result<Email, AuthAPIError> {
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(purpose, challenge)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })
+}
+
+async fn credential_descriptors(
+    state: &AppState,
+    user_id: &UserId,
+) -> Result<Vec<PublicKeyCredentialDescriptor>, AuthAPIError> {
+    let credentials = state
+        .passkey_store
+        .read()
+        .await
+        .get_credentials(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(credentials
+        .into_iter()
+        .map(|credential| PublicKeyCredentialDescriptor {
+            credential_type: "public-key".to_owned(),
+            id: URL_SAFE_NO_PAD.encode(credential.id),
+        })
+        .collect())
+}
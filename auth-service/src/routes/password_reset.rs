@@ -0,0 +1,145 @@
+use super::{end_all_sessions, record_audit_event, token_user};
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuditEventKind, AuthAPIError, ClientInfo, Email, OneTimeToken, OneTimeTokenStoreError,
+        Password, TokenPurpose, UserStoreError,
+    },
+    utils::PUBLIC_BASE_URL,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: Secret<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetConfirmRequest {
+    pub token: Secret<String>,
+    #[serde(rename = "newPassword")]
+    pub new_password: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordResetResponse {
+    pub message: String,
+}
+
+#[tracing::instrument(name = "Request Password Reset", skip_all)]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    Json(request): Json<PasswordResetRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    // Same answer whether or not the account exists, so the route can't be used to enumerate users
+    let response = Json(PasswordResetResponse {
+        message: "If the account exists, a password reset link has been sent".to_owned(),
+    });
+
+    let Ok(email) = Email::parse(request.email) else {
+        return Ok((StatusCode::OK, response));
+    };
+
+    match state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(&email)
+        .await
+    {
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return Ok((StatusCode::OK, response)),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    send_password_reset(&state, &email).await?;
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Email the user a link to choose a new password.
+#[tracing::instrument(name = "Send Password Reset", skip_all)]
+pub(crate) async fn send_password_reset(
+    state: &AppState,
+    email: &Email,
+) -> Result<(), AuthAPIError> {
+    let token = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(TokenPurpose::PasswordReset, &token, email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let subject = "Reset your password";
+    let content = format!(
+        "Use this link to choose a new password: {}/password-reset?token={}\n\
+         The link expires in {} minutes. If you did not ask for a reset, ignore this email.",
+        PUBLIC_BASE_URL.as_str(),
+        token.as_ref().expose_secret(),
+        TokenPurpose::PasswordReset.ttl_seconds() / 60
+    );
+    state
+        .email_client
+        .send_email(email, subject, &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
+
+#[tracing::instrument(name = "Confirm Password Reset", skip_all)]
+pub async fn confirm_password_reset(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(request): Json<PasswordResetConfirmRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    // Validate the new password first so a rejected password doesn't burn the token
+    let password =
+        Password::parse(request.new_password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let token = OneTimeToken::parse(request.token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(TokenPurpose::PasswordReset, &token)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    let user = token_user(&state, &email).await?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(&user.id, password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    // Following the emailed link proves the address belongs to the user
+    state
+        .user_store
+        .write()
+        .await
+        .mark_email_verified(&user.id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    // Whoever held the old password must lose every session they had
+    end_all_sessions(&state, &user.id).await?;
+    record_audit_event(&state, &user.id, AuditEventKind::PasswordReset, &client).await;
+
+    Ok((
+        StatusCode::OK,
+        Json(PasswordResetResponse {
+            message: "Password has been reset".to_owned(),
+        }),
+    ))
+}
@@ -0,0 +1,111 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, RecoveryCode, UserId, UserStoreError},
+    utils::AuthenticatedUser,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use secrecy::Secret;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesStatusResponse {
+    pub remaining: usize,
+}
+
+#[derive(Deserialize)]
+pub struct RegenerateRecoveryCodesRequest {
+    pub password: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+#[tracing::instrument(name = "Recovery Codes Status", skip_all)]
+pub async fn recovery_codes_status(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let remaining = state
+        .user_store
+        .read()
+        .await
+        .count_recovery_codes(&user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(RecoveryCodesStatusResponse { remaining }),
+    ))
+}
+
+#[tracing::instrument(name = "Regenerate Recovery Codes", skip_all)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    Json(request): Json<RegenerateRecoveryCodesRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    // Fresh codes bypass the second factor, so a session cookie alone isn't enough
+    state
+        .user_store
+        .read()
+        .await
+        .validate_user(&user_id, &request.password)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::InvalidCredentials | UserStoreError::UserNotFound => {
+                AuthAPIError::IncorrectCredentials
+            }
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let recovery_codes = issue_recovery_codes(&state, &user_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(RecoveryCodesResponse { recovery_codes }),
+    ))
+}
+
+/// Replace the user's recovery codes with a new set and return them for display.
+/// This is the only time the codes are available in clear.
+#[tracing::instrument(name = "Issue Recovery Codes", skip_all)]
+pub(crate) async fn issue_recovery_codes(
+    state: &AppState,
+    user_id: &UserId,
+) -> Result<Vec<String>, AuthAPIError> {
+    let codes = RecoveryCode::generate_set();
+    state
+        .user_store
+        .write()
+        .await
+        .replace_recovery_codes(user_id, &codes)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(codes.iter().map(RecoveryCode::display).collect())
+}
+
+/// Email recovery codes to their owner, for when 2FA was turned on by someone else.
+#[tracing::instrument(name = "Send Recovery Codes", skip_all)]
+pub(crate) async fn send_recovery_codes(
+    state: &AppState,
+    email: &Email,
+    codes: &[String],
+) -> Result<(), AuthAPIError> {
+    let subject = "Your recovery codes";
+    let content = format!(
+        "Two-factor authentication was turned on for your account. If you lose access to your \
+         second factor, each of these codes logs you in once:\n{}\n\
+         Keep them somewhere safe, or generate new ones from your account.",
+        codes.join("\n")
+    );
+    state
+        .email_client
+        .send_email(email, subject, &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
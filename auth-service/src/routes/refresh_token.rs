@@ -0,0 +1,115 @@
+use super::{current_user, ensure_account_active, user_roles, LoginResponse, SessionTokens};
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, ClientInfo, RefreshToken, RefreshTokenStoreError, Session, SessionStoreError,
+    },
+    utils::{
+        create_auth_cookie, create_refresh_cookie, issue_auth_token, REFRESH_TOKEN_COOKIE_NAME,
+    },
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_extra::extract::CookieJar;
+use secrecy::Secret;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct RefreshTokenRequest {
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: Secret<String>,
+}
+
+#[tracing::instrument(name = "Refresh Token", skip_all)]
+pub async fn refresh_token(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    jar: CookieJar,
+    request: Option<Json<RefreshTokenRequest>>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    // Browsers present the cookie; API clients without one send the token in the body
+    // and get the new tokens back the same way.
+    let (presented, from_cookie) = match (jar.get(REFRESH_TOKEN_COOKIE_NAME), request) {
+        (Some(cookie), _) => (Secret::new(cookie.value().to_owned()), true),
+        (None, Some(Json(request))) => (request.refresh_token, false),
+        (None, None) => return Err(AuthAPIError::MissingToken),
+    };
+    let presented = RefreshToken::parse(presented).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    // Every refresh hands out a new refresh token; the presented one is spent.
+    let replacement = RefreshToken::default();
+    let family = state
+        .refresh_token_store
+        .write()
+        .await
+        .rotate(&presented, &replacement)
+        .await
+        .map_err(|e| match e {
+            RefreshTokenStoreError::TokenNotFound | RefreshTokenStoreError::TokenReused => {
+                AuthAPIError::InvalidToken
+            }
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    // Revoked sessions are banned by id; their family must not mint new tokens either
+    let session_revoked = state
+        .banned_token_store
+        .read()
+        .await
+        .is_token_banned(&family.id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if session_revoked {
+        state
+            .refresh_token_store
+            .write()
+            .await
+            .revoke_family(&family.id)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    // The same account checks as at login, so disabling an account also stops its refreshes
+    let user = current_user(&state, &family.user_id).await?;
+    ensure_account_active(&user)?;
+
+    // The new JWT belongs to the same session as the refresh token family.
+    // Roles are looked up afresh, so role changes reach tokens on the next refresh.
+    let roles = user_roles(&state, &family.user_id).await?;
+    let access_token = {
+        let banned_store = state.banned_token_store.read().await;
+        issue_auth_token(&family.user_id, &family.id, &roles, &*banned_store)
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?
+    };
+
+    {
+        let mut session_store = state.session_store.write().await;
+        match session_store.touch_session(&family.id, &client).await {
+            Ok(()) => {}
+            // Families started before sessions were tracked get a session on their next refresh
+            Err(SessionStoreError::SessionNotFound) => session_store
+                .add_session(Session::new(family.id.clone(), family.user_id, &client))
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?,
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+    }
+
+    if !from_cookie {
+        let tokens = SessionTokens {
+            access_token,
+            refresh_token: replacement,
+        };
+        return Ok((
+            jar,
+            (StatusCode::OK, Json(LoginResponse::Token(tokens.into()))).into_response(),
+        ));
+    }
+
+    let updated_jar = jar
+        .add(create_auth_cookie(access_token))
+        .add(create_refresh_cookie(&replacement));
+
+    Ok((updated_jar, StatusCode::OK.into_response()))
+}
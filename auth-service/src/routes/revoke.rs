@@ -0,0 +1,74 @@
+use super::end_session;
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, RefreshToken, RefreshTokenStoreError},
+    utils::decode_claims,
+};
+use axum::{extract::State, http::StatusCode, Form};
+use secrecy::Secret;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct RevokeRequest {
+    pub token: String,
+    /// `access_token` or `refresh_token`; decides which kind is tried first
+    pub token_type_hint: Option<String>,
+}
+
+/// RFC 7009 token revocation, for clients that hold their tokens outside of cookies.
+/// Responds 200 whether or not the token was valid, so it can't be used to probe tokens.
+#[tracing::instrument(name = "Revoke Token", skip_all)]
+pub async fn revoke(
+    State(state): State<AppState>,
+    Form(request): Form<RevokeRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    // A wrong hint only costs a lookup, the other kind is tried next
+    if request.token_type_hint.as_deref() == Some("refresh_token") {
+        if !revoke_refresh_token(&state, &request.token).await? {
+            revoke_access_token(&state, &request.token).await?;
+        }
+    } else if !revoke_access_token(&state, &request.token).await? {
+        revoke_refresh_token(&state, &request.token).await?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Ban the access token until it expires. Returns whether `token` was an access token.
+async fn revoke_access_token(state: &AppState, token: &str) -> Result<bool, AuthAPIError> {
+    let Ok(claims) = decode_claims(token) else {
+        return Ok(false);
+    };
+
+    state
+        .banned_token_store
+        .write()
+        .await
+        .ban_if_not_present(&claims.jti, claims.exp)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    Ok(true)
+}
+
+/// End the session the refresh token belongs to, which also invalidates
+/// the access tokens issued to it. Returns whether `token` was a refresh token.
+async fn revoke_refresh_token(state: &AppState, token: &str) -> Result<bool, AuthAPIError> {
+    let Ok(refresh_token) = RefreshToken::parse(Secret::new(token.to_owned())) else {
+        return Ok(false);
+    };
+
+    let family = state
+        .refresh_token_store
+        .read()
+        .await
+        .get_family(&refresh_token)
+        .await;
+    match family {
+        Ok(family) => {
+            end_session(state, &family.id).await?;
+            Ok(true)
+        }
+        Err(RefreshTokenStoreError::TokenNotFound) => Ok(false),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
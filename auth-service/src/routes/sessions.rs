@@ -0,0 +1,165 @@
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, RefreshTokenStoreError, SessionStoreError},
+    utils::{latest_token_expiry, AuthenticatedUser, JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub id: String,
+    pub device: String,
+    pub ip: Option<String>,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "lastSeenAt")]
+    pub last_seen_at: i64,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevokeSessionResponse {
+    pub message: String,
+}
+
+#[tracing::instrument(name = "List Sessions", skip_all)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, claims }: AuthenticatedUser,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let sessions = state
+        .session_store
+        .read()
+        .await
+        .get_sessions(&user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    // A session lives as long as its refresh token family; a password reset, for one,
+    // revokes the families without knowing about sessions. Drop those that have ended.
+    let mut live = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let current_token = state
+            .refresh_token_store
+            .read()
+            .await
+            .get_current_token(&session.id)
+            .await;
+        match current_token {
+            Ok(_) => live.push(session),
+            Err(RefreshTokenStoreError::TokenNotFound) => {
+                remove_session(&state, &session.id).await?
+            }
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+    }
+
+    let sessions = live
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: session.id == claims.sid,
+            id: session.id,
+            device: session.device,
+            ip: session.ip,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(SessionsResponse { sessions })))
+}
+
+/// Log out one of the user's sessions, wherever it is.
+#[tracing::instrument(name = "Revoke Session", skip_all)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, claims }: AuthenticatedUser,
+    jar: CookieJar,
+    Path(session_id): Path<String>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    // Someone else's session is reported as missing, not forbidden, so ids can't be probed
+    let session = state
+        .session_store
+        .read()
+        .await
+        .get_session(&session_id)
+        .await
+        .map_err(|e| match e {
+            SessionStoreError::SessionNotFound => AuthAPIError::SessionNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    if session.user_id != user_id {
+        return Err(AuthAPIError::SessionNotFound);
+    }
+
+    end_session(&state, &session.id).await?;
+
+    // Revoking the current session is a logout
+    let jar = if session.id == claims.sid {
+        jar.remove(JWT_COOKIE_NAME)
+            .remove(REFRESH_TOKEN_COOKIE_NAME)
+    } else {
+        jar
+    };
+
+    Ok((
+        jar,
+        (
+            StatusCode::OK,
+            Json(RevokeSessionResponse {
+                message: "Session revoked".to_owned(),
+            }),
+        ),
+    ))
+}
+
+/// End a session for good: its refresh token family is revoked and its id banned,
+/// so JWTs already issued to it stop working too.
+#[tracing::instrument(name = "End Session", skip_all)]
+pub(crate) async fn end_session(state: &AppState, session_id: &str) -> Result<(), AuthAPIError> {
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_family(session_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    // Session ids are UUIDs like token ids, so they can share the banned token list
+    state
+        .banned_token_store
+        .write()
+        .await
+        .add_banned_token(session_id, latest_token_expiry())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    remove_session(state, session_id).await
+}
+
+async fn remove_session(state: &AppState, session_id: &str) -> Result<(), AuthAPIError> {
+    match state
+        .session_store
+        .write()
+        .await
+        .remove_session(session_id)
+        .await
+    {
+        Ok(()) | Err(SessionStoreError::SessionNotFound) => Ok(()),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
@@ -1,6 +1,7 @@
+use super::{issue_recovery_codes, record_audit_event, send_verification_email};
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, Password, User, UserStoreError},
+    domain::{AuditEventKind, AuthAPIError, ClientInfo, Email, Password, User, UserStoreError},
 };
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use secrecy::Secret;
@@ -17,31 +18,53 @@ pub struct SignupRequest {
 #[derive(Serialize, Deserialize)]
 pub struct SignupResponse {
     pub message: String,
+    /// Handed out once when the account is created with 2FA
+    #[serde(rename = "recoveryCodes", skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
 }
 
 //#[axum::debug_handler]
 #[tracing::instrument(name = "Signup", skip_all)]
 pub async fn signup(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(request): Json<SignupRequest>,
 ) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
     let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
     let password =
         Password::parse(request.password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let user = User::new(email.clone(), password, request.requires_2fa);
+    let user_id = user.id;
     state
         .user_store
         .write()
         .await
-        .add_user(User::new(email, password, request.requires_2fa))
+        .add_user(user)
         .await
         .map_err(|e| match e {
             UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
             _ => AuthAPIError::UnexpectedError(e.into()),
         })?;
+    record_audit_event(&state, &user_id, AuditEventKind::Signup, &client).await;
+
+    // The account exists either way; failing here would leave it unreachable behind a 409
+    if let Err(e) = send_verification_email(&state, &email).await {
+        tracing::error!(
+            "Failed to send verification email, the user can ask for another: {:?}",
+            e
+        );
+    }
+
+    let recovery_codes = match request.requires_2fa {
+        true => Some(issue_recovery_codes(&state, &user_id).await?),
+        false => None,
+    };
+
     Ok((
         StatusCode::CREATED,
         Json(SignupResponse {
             message: "User created successfully!".to_string(),
+            recovery_codes,
         }),
     ))
 }
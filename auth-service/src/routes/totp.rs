@@ -0,0 +1,91 @@
+use super::{current_user, issue_recovery_codes};
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, TotpSecret, TwoFACode},
+    utils::AuthenticatedUser,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use chrono::Utc;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpEnrollResponse {
+    pub secret: String,
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+#[derive(Deserialize)]
+pub struct TotpConfirmRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpConfirmResponse {
+    pub message: String,
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+/// Start TOTP enrollment. The new secret stays pending, and any active one keeps working,
+/// until the user proves their app is set up by confirming a code.
+#[tracing::instrument(name = "Enroll TOTP", skip_all)]
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let email = current_user(&state, &user_id).await?.email;
+    let secret = TotpSecret::default();
+    state
+        .user_store
+        .write()
+        .await
+        .set_pending_totp_secret(&user_id, &secret)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(TotpEnrollResponse {
+            secret: secret.to_base32().expose_secret().to_owned(),
+            otpauth_uri: secret.otpauth_uri(&email).expose_secret().to_owned(),
+        }),
+    ))
+}
+
+#[tracing::instrument(name = "Confirm TOTP", skip_all)]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    Json(request): Json<TotpConfirmRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let code = TwoFACode::parse(&request.code).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    // Hold the write lock so a concurrent enrollment can't swap the secret being confirmed
+    let mut user_store = state.user_store.write().await;
+    let secret = user_store
+        .get_pending_totp_secret(&user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+        .ok_or(AuthAPIError::InvalidCredentials)?;
+    let step = secret
+        .verify(&code, Utc::now().timestamp().unsigned_abs())
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+
+    user_store
+        .activate_totp(&user_id, step)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+
+    let recovery_codes = issue_recovery_codes(&state, &user_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(TotpConfirmResponse {
+            message: "TOTP two-factor authentication enabled".to_owned(),
+            recovery_codes,
+        }),
+    ))
+}
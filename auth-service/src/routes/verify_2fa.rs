@@ -1,49 +1,161 @@
+use super::{issue_session, start_session, LoginResponse};
 use crate::app_state::AppState;
-use crate::domain::{AuthAPIError, Email};
-use crate::utils::generate_auth_cookie;
+use crate::domain::{
+    AuthAPIError, ClientInfo, Email, RecoveryCode, TwoFACode, TwoFAMethod, UserId,
+};
+use crate::utils::verify_password_hash;
 use axum::extract::State;
 use axum::{http::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::CookieJar;
+use chrono::Utc;
 use secrecy::Secret;
 use serde::Deserialize;
 
 #[tracing::instrument(name = "Verify 2FA", skip_all)]
 pub async fn verify_2fa(
     State(state): State<AppState>,
+    client: ClientInfo,
     jar: CookieJar,
     Json(request): Json<Verify2FARequest>,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let user_id = check_second_factor(&state, request).await?;
+    let updated_jar = start_session(&user_id, &client, &state, jar).await?;
+
+    Ok((updated_jar, StatusCode::OK.into_response()))
+}
+
+/// Same as `verify_2fa`, but the session is returned as tokens in the body instead of cookies.
+#[tracing::instrument(name = "Verify 2FA For Token", skip_all)]
+pub async fn verify_2fa_token(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(request): Json<Verify2FARequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let user_id = check_second_factor(&state, request).await?;
+    let tokens = issue_session(&user_id, &client, &state).await?;
+
+    Ok((StatusCode::OK, Json(LoginResponse::Token(tokens.into()))))
+}
+
+/// Check the second factor of a login attempt and close the attempt, returning whose it was.
+#[tracing::instrument(name = "Check Second Factor", skip_all)]
+async fn check_second_factor(
+    state: &AppState,
+    request: Verify2FARequest,
+) -> Result<UserId, AuthAPIError> {
     let email =
         Email::parse(Secret::new(request.email)).map_err(|_| AuthAPIError::IncorrectCredentials)?;
 
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(&email)
+        .await
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+
     let (stored_login_attempt_id, stored_two_fa_code) = state
         .two_fa_code_store
         .read()
         .await
-        .get_code(&email)
+        .get_code(&user.id)
         .await
         .map_err(|_| AuthAPIError::IncorrectCredentials)?;
 
-    if stored_login_attempt_id.as_ref() != request.login_attempt_id
-        || stored_two_fa_code.as_ref() != request.two_fa_code
-    {
+    if stored_login_attempt_id.as_ref() != request.login_attempt_id {
         return Err(AuthAPIError::IncorrectCredentials);
     }
 
+    // A recovery code stands in for whichever second factor the user has
+    if let Ok(recovery_code) = RecoveryCode::parse(&request.two_fa_code) {
+        verify_recovery_code(state, &user.id, &recovery_code).await?;
+    } else {
+        match user.two_fa_method {
+            TwoFAMethod::Email => {
+                if stored_two_fa_code.as_ref() != request.two_fa_code {
+                    return Err(AuthAPIError::IncorrectCredentials);
+                }
+            }
+            TwoFAMethod::Totp => verify_totp(state, &user.id, &request.two_fa_code).await?,
+        }
+    }
+
     state
         .two_fa_code_store
         .write()
         .await
-        .remove_code(&email)
+        .remove_code(&user.id)
         .await
         .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
 
-    let auth_cookie =
-        generate_auth_cookie(&email).map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    Ok(user.id)
+}
 
-    let updated_jar = jar.add(auth_cookie);
+#[tracing::instrument(name = "Verify TOTP", skip_all)]
+async fn verify_totp(state: &AppState, user_id: &UserId, code: &str) -> Result<(), AuthAPIError> {
+    let code = TwoFACode::parse(code).map_err(|_| AuthAPIError::IncorrectCredentials)?;
 
-    Ok((updated_jar, StatusCode::OK.into_response()))
+    let mut user_store = state.user_store.write().await;
+    let secret = user_store
+        .get_totp_secret(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+    let step = secret
+        .verify(&code, Utc::now().timestamp().unsigned_abs())
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+
+    // A code seen once must not work again, even inside its 30 second window
+    let fresh = user_store
+        .record_totp_step(user_id, step)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if !fresh {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Verify recovery code", skip_all)]
+async fn verify_recovery_code(
+    state: &AppState,
+    user_id: &UserId,
+    code: &RecoveryCode,
+) -> Result<(), AuthAPIError> {
+    let stored_codes = state
+        .user_store
+        .read()
+        .await
+        .get_recovery_codes(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    // Hashes are checked with no lock held; each check takes a while
+    let mut matched = None;
+    for stored in stored_codes {
+        if verify_password_hash(&stored.code_hash, code.as_ref())
+            .await
+            .is_ok()
+        {
+            matched = Some(stored.id);
+            break;
+        }
+    }
+    let code_id = matched.ok_or(AuthAPIError::IncorrectCredentials)?;
+
+    let spent = state
+        .user_store
+        .write()
+        .await
+        .use_recovery_code(user_id, code_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if !spent {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    Ok(())
 }
 
 #[derive(Deserialize)]
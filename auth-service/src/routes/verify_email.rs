@@ -0,0 +1,137 @@
+use super::token_user;
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, OneTimeToken, OneTimeTokenStoreError, TokenPurpose, UserStoreError,
+    },
+    utils::{sign_email_verification_token, verify_email_verification_token, PUBLIC_BASE_URL},
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyEmailResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResendVerificationRequest {
+    pub email: Secret<String>,
+}
+
+/// Served as GET because the link in the verification email is opened directly.
+#[tracing::instrument(name = "Verify Email", skip_all)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    // Forged or altered links are rejected before the single-use token is consumed
+    let token = verify_email_verification_token(query.token.expose_secret())
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let email = state
+        .one_time_token_store
+        .write()
+        .await
+        .consume_token(TokenPurpose::EmailVerification, &token)
+        .await
+        .map_err(|e| match e {
+            OneTimeTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+    let user = token_user(&state, &email).await?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .mark_email_verified(&user.id)
+        .await
+        .map_err(|e| match e {
+            UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok((
+        StatusCode::OK,
+        Json(VerifyEmailResponse {
+            message: "Email verified".to_owned(),
+        }),
+    ))
+}
+
+/// Send a fresh verification link, e.g. when the one from signup never arrived or expired.
+#[tracing::instrument(name = "Resend Verification Email", skip_all)]
+pub async fn resend_verification_email(
+    State(state): State<AppState>,
+    Json(request): Json<ResendVerificationRequest>,
+) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    // Same answer for unknown and already verified accounts, so the route can't enumerate users
+    let response = Json(VerifyEmailResponse {
+        message: "If the account exists and is unverified, a verification link has been sent"
+            .to_owned(),
+    });
+
+    let Ok(email) = Email::parse(request.email) else {
+        return Ok((StatusCode::OK, response));
+    };
+
+    let user = match state
+        .user_store
+        .read()
+        .await
+        .get_user_by_email(&email)
+        .await
+    {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Ok((StatusCode::OK, response)),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    if !user.email_verified {
+        send_verification_email(&state, &user.email).await?;
+    }
+
+    Ok((StatusCode::OK, response))
+}
+
+/// Email the user a link that proves the address is theirs.
+#[tracing::instrument(name = "Send Verification Email", skip_all)]
+pub(crate) async fn send_verification_email(
+    state: &AppState,
+    email: &Email,
+) -> Result<(), AuthAPIError> {
+    let token = OneTimeToken::default();
+    state
+        .one_time_token_store
+        .write()
+        .await
+        .add_token(TokenPurpose::EmailVerification, &token, email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    let signed = sign_email_verification_token(&token).map_err(AuthAPIError::UnexpectedError)?;
+
+    let subject = "Verify your email address";
+    let content = format!(
+        "Confirm that this address belongs to you: {}/verify-email?token={}\n\
+         The link expires in {} hours.",
+        PUBLIC_BASE_URL.as_str(),
+        signed,
+        TokenPurpose::EmailVerification.ttl_seconds() / 3600
+    );
+    state
+        .email_client
+        .send_email(email, subject, &content)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
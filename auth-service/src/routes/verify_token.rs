@@ -1,4 +1,8 @@
-use crate::{app_state::AppState, domain::AuthAPIError, utils::validate_token};
+use crate::{
+    app_state::AppState,
+    domain::AuthAPIError,
+    utils::{validate_token_for, JWT_AUDIENCE},
+};
 use axum::{
     extract::{Json, State},
     http::StatusCode,
@@ -9,6 +13,8 @@ use serde::Deserialize;
 #[derive(Deserialize)]
 pub struct VerifyTokenRequest {
     token: String,
+    /// The service the token must be for; this service's own audience when absent
+    audience: Option<String>,
 }
 
 #[tracing::instrument(name = "Verify Token", skip_all)]
@@ -16,8 +22,9 @@ pub async fn verify_token(
     State(app_state): State<AppState>,
     Json(request): Json<VerifyTokenRequest>,
 ) -> Result<(StatusCode, impl IntoResponse), AuthAPIError> {
+    let audience = request.audience.as_deref().unwrap_or(&JWT_AUDIENCE);
     let banned_store = app_state.banned_token_store.read().await;
-    validate_token(&request.token, &*banned_store)
+    validate_token_for(&request.token, &[audience], &*banned_store)
         .await
         .map_err(|_| AuthAPIError::InvalidToken)?;
     Ok((StatusCode::OK, Json("Token valid".to_string())))
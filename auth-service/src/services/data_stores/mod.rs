@@ -1,7 +1,23 @@
+mod postgres_audit_event_store;
+mod postgres_consent_store;
+mod postgres_passkey_store;
 mod postgres_user_store;
 mod redis_banned_token_store;
+mod redis_magic_link_store;
+mod redis_one_time_token_store;
+mod redis_rate_limit_store;
+mod redis_refresh_token_store;
+mod redis_session_store;
 mod redis_two_fa_code_store;
 
+pub use postgres_audit_event_store::*;
+pub use postgres_consent_store::*;
+pub use postgres_passkey_store::*;
 pub use postgres_user_store::*;
 pub use redis_banned_token_store::*;
+pub use redis_magic_link_store::*;
+pub use redis_one_time_token_store::*;
+pub use redis_rate_limit_store::*;
+pub use redis_refresh_token_store::*;
+pub use redis_session_store::*;
 pub use redis_two_fa_code_store::*;
@@ -0,0 +1,79 @@
+use crate::domain::{
+    AuditEventKind, AuditEventStore, AuditEventStoreError, ClientInfo, PersonalDataExporter, UserId,
+};
+use color_eyre::eyre::{eyre, Report};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub struct PostgresAuditEventStore {
+    pool: PgPool,
+}
+
+impl PostgresAuditEventStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for PostgresAuditEventStore {
+    fn export_section(&self) -> &'static str {
+        "auditEvents"
+    }
+
+    #[tracing::instrument(name = "Export Audit Events", skip_all)]
+    async fn export_personal_data(&self, user_id: &UserId) -> Result<Option<Value>, Report> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT event, ip, user_agent,
+                EXTRACT(EPOCH FROM occurred_at)::BIGINT as "occurred_at!"
+            FROM audit_events
+            WHERE user_id = $1
+            ORDER BY occurred_at, id
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let events: Vec<Value> = rows
+            .into_iter()
+            .map(|row| {
+                json!({
+                    "event": row.event,
+                    "ip": row.ip,
+                    "userAgent": row.user_agent,
+                    "occurredAt": row.occurred_at,
+                })
+            })
+            .collect();
+        Ok(Some(Value::Array(events)))
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditEventStore for PostgresAuditEventStore {
+    #[tracing::instrument(name = "Recording audit event in PostgreSQL", skip_all)]
+    async fn record_event(
+        &mut self,
+        user_id: &UserId,
+        kind: AuditEventKind,
+        client: &ClientInfo,
+    ) -> Result<(), AuditEventStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_events (user_id, event, ip, user_agent)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            user_id.as_ref(),
+            kind.as_str(),
+            client.ip,
+            client.user_agent
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AuditEventStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(())
+    }
+}
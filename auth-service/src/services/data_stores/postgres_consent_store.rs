@@ -0,0 +1,74 @@
+use crate::domain::{
+    Consent, ConsentPurpose, ConsentStore, ConsentStoreError, PersonalDataExporter, UserId,
+};
+use color_eyre::eyre::{eyre, Report};
+use serde_json::Value;
+use sqlx::PgPool;
+
+pub struct PostgresConsentStore {
+    pool: PgPool,
+}
+
+impl PostgresConsentStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for PostgresConsentStore {
+    fn export_section(&self) -> &'static str {
+        "consents"
+    }
+
+    #[tracing::instrument(name = "Export Consents", skip_all)]
+    async fn export_personal_data(&self, user_id: &UserId) -> Result<Option<Value>, Report> {
+        let consents = self.get_consents(user_id).await?;
+        Ok(Some(serde_json::to_value(consents)?))
+    }
+}
+
+#[async_trait::async_trait]
+impl ConsentStore for PostgresConsentStore {
+    #[tracing::instrument(name = "Setting consent in PostgreSQL", skip_all)]
+    async fn set_consent(
+        &mut self,
+        user_id: &UserId,
+        purpose: &ConsentPurpose,
+        granted: bool,
+    ) -> Result<(), ConsentStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO consents (user_id, purpose, granted)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, purpose) DO UPDATE SET granted = $3, updated_at = NOW()
+            "#,
+            user_id.as_ref(),
+            purpose.as_ref(),
+            granted
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ConsentStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving consents from PostgreSQL", skip_all)]
+    async fn get_consents(&self, user_id: &UserId) -> Result<Vec<Consent>, ConsentStoreError> {
+        sqlx::query_as!(
+            Consent,
+            r#"
+            SELECT purpose, granted,
+                EXTRACT(EPOCH FROM updated_at)::BIGINT as "updated_at!"
+            FROM consents
+            WHERE user_id = $1
+            ORDER BY purpose
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ConsentStoreError::UnexpectedError(eyre!(e)))
+    }
+}
@@ -0,0 +1,177 @@
+use crate::domain::{
+    PasskeyCredential, PasskeyPublicKey, PasskeyStore, PasskeyStoreError, PersonalDataExporter,
+    UserId,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use color_eyre::eyre::{eyre, Report};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+pub struct PostgresPasskeyStore {
+    pool: PgPool,
+}
+
+impl PostgresPasskeyStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+struct PasskeyCredentialRow {
+    credential_id: Vec<u8>,
+    user_id: UserId,
+    algorithm: i32,
+    public_key: Vec<u8>,
+    sign_count: i64,
+}
+
+impl TryFrom<PasskeyCredentialRow> for PasskeyCredential {
+    type Error = PasskeyStoreError;
+
+    fn try_from(row: PasskeyCredentialRow) -> Result<Self, Self::Error> {
+        Ok(PasskeyCredential {
+            id: row.credential_id,
+            user_id: row.user_id,
+            public_key: PasskeyPublicKey::from_parts(row.algorithm.into(), row.public_key)
+                .map_err(PasskeyStoreError::UnexpectedError)?,
+            sign_count: u32::try_from(row.sign_count)
+                .map_err(|e| PasskeyStoreError::UnexpectedError(eyre!(e)))?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for PostgresPasskeyStore {
+    fn export_section(&self) -> &'static str {
+        "passkeys"
+    }
+
+    #[tracing::instrument(name = "Export Passkeys", skip_all)]
+    async fn export_personal_data(&self, user_id: &UserId) -> Result<Option<Value>, Report> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT credential_id, algorithm,
+                EXTRACT(EPOCH FROM created_at)::BIGINT as "created_at!",
+                EXTRACT(EPOCH FROM last_used_at)::BIGINT as last_used_at
+            FROM passkey_credentials
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let passkeys: Vec<Value> = rows
+            .into_iter()
+            .map(|row| {
+                json!({
+                    "id": URL_SAFE_NO_PAD.encode(row.credential_id),
+                    "algorithm": row.algorithm,
+                    "createdAt": row.created_at,
+                    "lastUsedAt": row.last_used_at,
+                })
+            })
+            .collect();
+        Ok(Some(Value::Array(passkeys)))
+    }
+}
+
+#[async_trait::async_trait]
+impl PasskeyStore for PostgresPasskeyStore {
+    #[tracing::instrument(name = "Adding passkey credential to PostgreSQL", skip_all)]
+    async fn add_credential(
+        &mut self,
+        credential: PasskeyCredential,
+    ) -> Result<(), PasskeyStoreError> {
+        let algorithm = i32::try_from(credential.public_key.algorithm())
+            .map_err(|e| PasskeyStoreError::UnexpectedError(eyre!(e)))?;
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO passkey_credentials (credential_id, user_id, algorithm, public_key, sign_count)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (credential_id) DO NOTHING
+            "#,
+            credential.id,
+            credential.user_id.as_ref(),
+            algorithm,
+            credential.public_key.as_bytes(),
+            i64::from(credential.sign_count)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PasskeyStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(PasskeyStoreError::CredentialAlreadyExists);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving passkey credential from PostgreSQL", skip_all)]
+    async fn get_credential(&self, id: &[u8]) -> Result<PasskeyCredential, PasskeyStoreError> {
+        sqlx::query_as!(
+            PasskeyCredentialRow,
+            r#"
+            SELECT credential_id, user_id as "user_id: _", algorithm, public_key, sign_count
+            FROM passkey_credentials
+            WHERE credential_id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| PasskeyStoreError::UnexpectedError(eyre!(e)))?
+        .ok_or(PasskeyStoreError::CredentialNotFound)?
+        .try_into()
+    }
+
+    #[tracing::instrument(name = "Retrieving user passkey credentials from PostgreSQL", skip_all)]
+    async fn get_credentials(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<PasskeyCredential>, PasskeyStoreError> {
+        sqlx::query_as!(
+            PasskeyCredentialRow,
+            r#"
+            SELECT credential_id, user_id as "user_id: _", algorithm, public_key, sign_count
+            FROM passkey_credentials
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| PasskeyStoreError::UnexpectedError(eyre!(e)))?
+        .into_iter()
+        .map(PasskeyCredential::try_from)
+        .collect()
+    }
+
+    #[tracing::instrument(name = "Updating passkey sign count in PostgreSQL", skip_all)]
+    async fn update_sign_count(
+        &mut self,
+        id: &[u8],
+        sign_count: u32,
+    ) -> Result<(), PasskeyStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE passkey_credentials SET sign_count = $2, last_used_at = NOW()
+            WHERE credential_id = $1
+            "#,
+            id,
+            i64::from(sign_count)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PasskeyStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(PasskeyStoreError::CredentialNotFound);
+        }
+
+        Ok(())
+    }
+}
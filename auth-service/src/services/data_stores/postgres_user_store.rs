@@ -1,11 +1,16 @@
-use crate::domain::{Email, Password, User, UserStore, UserStoreError};
-use argon2::{
-    password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash, PasswordHasher,
-    PasswordVerifier, Version,
+use crate::{
+    domain::{
+        Email, Password, PersonalDataExporter, RecoveryCode, Role, RoleDefinition,
+        StoredRecoveryCode, TotpSecret, TwoFAMethod, User, UserId, UserPage, UserRoles, UserStore,
+        UserStoreError, EMAIL_CHANGE_REVERT_SECONDS,
+    },
+    utils::{compute_password_hash, decrypt, encrypt, verify_password_hash, TOTP_ENCRYPTION_KEY},
 };
-use color_eyre::eyre::{eyre, Context, Result};
+use color_eyre::eyre::{eyre, Context, Report, Result};
 use secrecy::{ExposeSecret, Secret};
+use serde_json::{json, Value};
 use sqlx::{PgPool, Row};
+use uuid::Uuid;
 
 pub struct PostgresUserStore {
     pool: PgPool,
@@ -22,6 +27,53 @@ struct UserPasswordRow {
     password_hash: String,
 }
 
+#[async_trait::async_trait]
+impl PersonalDataExporter for PostgresUserStore {
+    fn export_section(&self) -> &'static str {
+        "account"
+    }
+
+    #[tracing::instrument(name = "Export Account", skip_all)]
+    async fn export_personal_data(&self, user_id: &UserId) -> Result<Option<Value>, Report> {
+        // Every column but the password hash and TOTP secrets, which are only reported as present
+        let row = sqlx::query!(
+            r#"
+            SELECT email, email_verified, pending_email, previous_email, requires_2fa, two_fa_method,
+                totp_secret IS NOT NULL as "totp_enabled!", disabled, password_reset_required,
+                EXTRACT(EPOCH FROM delete_after)::BIGINT as delete_after
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let roles = self.get_roles(user_id).await?;
+        let recovery_codes = self.count_recovery_codes(user_id).await?;
+
+        Ok(Some(json!({
+            "id": user_id.to_string(),
+            "email": row.email,
+            "emailVerified": row.email_verified,
+            "pendingEmail": row.pending_email,
+            "previousEmail": row.previous_email,
+            "disabled": row.disabled,
+            "passwordResetRequired": row.password_reset_required,
+            "deleteAfter": row.delete_after,
+            "roles": roles.roles,
+            "twoFactor": {
+                "required": row.requires_2fa,
+                "method": row.two_fa_method,
+                "totpEnabled": row.totp_enabled,
+                "unusedRecoveryCodes": recovery_codes,
+            },
+        })))
+    }
+}
+
 #[async_trait::async_trait]
 impl UserStore for PostgresUserStore {
     #[tracing::instrument(name = "Adding user to PostgreSQL", skip_all)]
@@ -31,15 +83,23 @@ impl UserStore for PostgresUserStore {
             .await
             .map_err(UserStoreError::UnexpectedError)?;
 
+        // An address left by an email change stays reserved while the change can be undone
         let result = sqlx::query!(
             r#"
-            INSERT INTO users (email, password_hash, requires_2fa)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (email) DO NOTHING
+            INSERT INTO users (id, email, password_hash, requires_2fa)
+            SELECT $1, $2, $3, $4
+            WHERE NOT EXISTS (
+                SELECT 1 FROM users
+                WHERE lower(previous_email) = lower($2)
+                    AND email_changed_at > NOW() - $5 * INTERVAL '1 second'
+            )
+            ON CONFLICT (lower(email)) DO NOTHING
             "#,
+            user.id.as_ref(),
             user.email.as_ref().expose_secret(),
             password_hash,
-            user.requires_2fa
+            user.requires_2fa,
+            EMAIL_CHANGE_REVERT_SECONDS as f64
         )
         .execute(&self.pool)
         .await
@@ -53,13 +113,35 @@ impl UserStore for PostgresUserStore {
     }
 
     #[tracing::instrument(name = "Retrieving user from PostgreSQL", skip_all)]
-    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+    async fn get_user(&self, user_id: &UserId) -> Result<User, UserStoreError> {
+        let row = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id as "id: _", email as "email: _", password_hash as "password: _",
+                requires_2fa, email_verified, two_fa_method as "two_fa_method: _", disabled,
+                password_reset_required, delete_after IS NOT NULL as "pending_deletion!"
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        row.ok_or(UserStoreError::UserNotFound)
+    }
+
+    #[tracing::instrument(name = "Retrieving user by email from PostgreSQL", skip_all)]
+    async fn get_user_by_email(&self, email: &Email) -> Result<User, UserStoreError> {
         let row = sqlx::query_as!(
             User,
             r#"
-            SELECT email as "email: _", password_hash as "password: _", requires_2fa
+            SELECT id as "id: _", email as "email: _", password_hash as "password: _",
+                requires_2fa, email_verified, two_fa_method as "two_fa_method: _", disabled,
+                password_reset_required, delete_after IS NOT NULL as "pending_deletion!"
             FROM users
-            WHERE email = $1
+            WHERE lower(email) = lower($1)
             "#,
             email.as_ref().expose_secret()
         )
@@ -73,15 +155,15 @@ impl UserStore for PostgresUserStore {
     #[tracing::instrument(name = "Validating user credentials in PostgreSQL", skip_all)]
     async fn validate_user(
         &self,
-        email: &Email,
-        password: &Password,
+        user_id: &UserId,
+        password: &Secret<String>,
     ) -> Result<(), UserStoreError> {
         let row = sqlx::query_as!(
             UserPasswordRow,
             r#"
-            SELECT password_hash FROM users WHERE email = $1
+            SELECT password_hash FROM users WHERE id = $1
             "#,
-            email.as_ref().expose_secret()
+            user_id.as_ref()
         )
         .fetch_optional(&self.pool)
         .await
@@ -94,7 +176,7 @@ impl UserStore for PostgresUserStore {
 
         // Wrap the stored hash in a Secret to avoid exposing raw strings at the callsite.
         let stored = Secret::new(password_hash);
-        verify_password_hash(&stored, password.as_ref())
+        verify_password_hash(&stored, password)
             .await
             .map_err(|_| UserStoreError::InvalidCredentials)
     }
@@ -102,29 +184,37 @@ impl UserStore for PostgresUserStore {
     #[tracing::instrument(name = "Authenticating user from PostgreSQL", skip_all)]
     async fn authenticate_user(
         &self,
-        email: &str,
+        email: &Email,
         password: &Secret<String>,
     ) -> Result<User, UserStoreError> {
         let row = sqlx::query(
             r#"
-            SELECT email, password_hash, requires_2fa
+            SELECT id, email, password_hash, requires_2fa, email_verified, two_fa_method, disabled,
+                password_reset_required, delete_after IS NOT NULL as pending_deletion
             FROM users
-            WHERE email = $1
+            WHERE lower(email) = lower($1)
             "#,
         )
-        .bind(email as &str)
+        .bind(email.as_ref().expose_secret())
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?
         .ok_or(UserStoreError::UserNotFound)?;
 
         let user = User {
+            id: row.get::<Uuid, _>("id").into(),
             email: Email::parse(Secret::new(row.get::<&str, _>("email").to_string()))
                 .map_err(|_| UserStoreError::InvalidCredentials)?,
             // Keep the DB password hash wrapped as a Secret when parsing into `Password`.
             password: Password::parse(Secret::new(row.get::<&str, _>("password_hash").to_string()))
                 .map_err(|_| UserStoreError::InvalidCredentials)?,
             requires_2fa: row.get::<bool, _>("requires_2fa"),
+            email_verified: row.get::<bool, _>("email_verified"),
+            two_fa_method: TwoFAMethod::parse(row.get::<&str, _>("two_fa_method"))
+                .map_err(UserStoreError::UnexpectedError)?,
+            disabled: row.get::<bool, _>("disabled"),
+            password_reset_required: row.get::<bool, _>("password_reset_required"),
+            pending_deletion: row.get::<bool, _>("pending_deletion"),
         };
 
         // Compare the stored password hash (wrapped) with the incoming secret password.
@@ -134,48 +224,699 @@ impl UserStore for PostgresUserStore {
 
         Ok(user)
     }
-}
 
-#[tracing::instrument(name = "Verify password hash", skip_all)]
-async fn verify_password_hash(
-    expected_password_hash: &Secret<String>,
-    password_candidate: &Secret<String>,
-) -> Result<()> {
-    let current_span: tracing::Span = tracing::Span::current();
-
-    // Clone the inner strings here (safe within the current scope) and move into blocking code.
-    let expected_clone = expected_password_hash.expose_secret().clone();
-    let candidate_clone = password_candidate.expose_secret().clone();
-
-    tokio::task::spawn_blocking(move || {
-        current_span.in_scope(|| {
-            let parsed_hash = PasswordHash::new(expected_clone.as_str())?;
-            Argon2::default()
-                .verify_password(candidate_clone.as_bytes(), &parsed_hash)
-                .wrap_err("failed to verify password hash")
+    #[tracing::instrument(name = "Updating user password in PostgreSQL", skip_all)]
+    async fn update_password(
+        &mut self,
+        user_id: &UserId,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        let password_hash = compute_password_hash(password.as_ref().clone())
+            .await
+            .map_err(UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET password_hash = $2, password_reset_required = FALSE WHERE id = $1
+            "#,
+            user_id.as_ref(),
+            password_hash
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Marking user email as verified in PostgreSQL", skip_all)]
+    async fn mark_email_verified(&mut self, user_id: &UserId) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET email_verified = TRUE WHERE id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Storing pending TOTP secret in PostgreSQL", skip_all)]
+    async fn set_pending_totp_secret(
+        &mut self,
+        user_id: &UserId,
+        secret: &TotpSecret,
+    ) -> Result<(), UserStoreError> {
+        let encrypted = encrypt(&TOTP_ENCRYPTION_KEY, secret.as_ref().expose_secret())
+            .map_err(UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET pending_totp_secret = $2 WHERE id = $1
+            "#,
+            user_id.as_ref(),
+            encrypted
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving pending TOTP secret from PostgreSQL", skip_all)]
+    async fn get_pending_totp_secret(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Option<TotpSecret>, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT pending_totp_secret FROM users WHERE id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        row.pending_totp_secret
+            .map(|encrypted| decrypt_totp_secret(&encrypted))
+            .transpose()
+    }
+
+    #[tracing::instrument(name = "Retrieving TOTP secret from PostgreSQL", skip_all)]
+    async fn get_totp_secret(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Option<TotpSecret>, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT totp_secret FROM users WHERE id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        row.totp_secret
+            .map(|encrypted| decrypt_totp_secret(&encrypted))
+            .transpose()
+    }
+
+    #[tracing::instrument(name = "Activating TOTP in PostgreSQL", skip_all)]
+    async fn activate_totp(&mut self, user_id: &UserId, step: u64) -> Result<(), UserStoreError> {
+        let step = i64::try_from(step).map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_secret = pending_totp_secret,
+                pending_totp_secret = NULL,
+                totp_last_step = $2,
+                two_fa_method = 'totp',
+                requires_2fa = TRUE
+            WHERE id = $1 AND pending_totp_secret IS NOT NULL
+            "#,
+            user_id.as_ref(),
+            step
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Recording TOTP step in PostgreSQL", skip_all)]
+    async fn record_totp_step(
+        &mut self,
+        user_id: &UserId,
+        step: u64,
+    ) -> Result<bool, UserStoreError> {
+        let step = i64::try_from(step).map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+        // Conditional update keeps check-and-set atomic across concurrent logins
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET totp_last_step = $2
+            WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)
+            "#,
+            user_id.as_ref(),
+            step
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[tracing::instrument(name = "Replacing recovery codes in PostgreSQL", skip_all)]
+    async fn replace_recovery_codes(
+        &mut self,
+        user_id: &UserId,
+        codes: &[RecoveryCode],
+    ) -> Result<(), UserStoreError> {
+        // Recovery codes are hashed like passwords; only the user ever sees them in clear.
+        // Hash them concurrently, each hash runs on its own blocking thread.
+        let hash_tasks: Vec<_> = codes
+            .iter()
+            .map(|code| tokio::spawn(compute_password_hash(code.as_ref().clone())))
+            .collect();
+        let mut code_hashes = Vec::with_capacity(codes.len());
+        for task in hash_tasks {
+            code_hashes.push(
+                task.await
+                    .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?
+                    .map_err(UserStoreError::UnexpectedError)?,
+            );
+        }
+
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+        sqlx::query!(
+            r#"
+            DELETE FROM recovery_codes WHERE user_id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+        sqlx::query!(
+            r#"
+            INSERT INTO recovery_codes (user_id, code_hash)
+            SELECT $1, code_hash FROM UNNEST($2::TEXT[]) AS code_hash
+            "#,
+            user_id.as_ref(),
+            &code_hashes
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                UserStoreError::UserNotFound
+            }
+            e => UserStoreError::UnexpectedError(eyre!(e)),
+        })?;
+        transaction
+            .commit()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving recovery codes from PostgreSQL", skip_all)]
+    async fn get_recovery_codes(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<StoredRecoveryCode>, UserStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, code_hash FROM recovery_codes WHERE user_id = $1 AND used_at IS NULL
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| StoredRecoveryCode {
+                id: row.id,
+                code_hash: Secret::new(row.code_hash),
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(name = "Using recovery code in PostgreSQL", skip_all)]
+    async fn use_recovery_code(
+        &mut self,
+        user_id: &UserId,
+        code_id: i64,
+    ) -> Result<bool, UserStoreError> {
+        // Guard on used_at so two concurrent logins can't both spend the same code
+        let result = sqlx::query!(
+            r#"
+            UPDATE recovery_codes SET used_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND used_at IS NULL
+            "#,
+            code_id,
+            user_id.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[tracing::instrument(name = "Counting recovery codes in PostgreSQL", skip_all)]
+    async fn count_recovery_codes(&self, user_id: &UserId) -> Result<usize, UserStoreError> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM recovery_codes WHERE user_id = $1 AND used_at IS NULL
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        usize::try_from(count).map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))
+    }
+
+    #[tracing::instrument(name = "Retrieving user roles from PostgreSQL", skip_all)]
+    async fn get_roles(&self, user_id: &UserId) -> Result<UserRoles, UserStoreError> {
+        let roles = sqlx::query_scalar!(
+            r#"
+            SELECT role FROM user_roles WHERE user_id = $1 ORDER BY role
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        let permissions = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT rp.permission
+            FROM user_roles ur
+            JOIN role_permissions rp ON rp.role = ur.role
+            WHERE ur.user_id = $1
+            ORDER BY rp.permission
+            "#,
+            user_id.as_ref()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(UserRoles { roles, permissions })
+    }
+
+    #[tracing::instrument(name = "Assigning role in PostgreSQL", skip_all)]
+    async fn assign_role(&mut self, user_id: &UserId, role: &Role) -> Result<bool, UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role) VALUES ($1, $2)
+            ON CONFLICT (user_id, role) DO NOTHING
+            "#,
+            user_id.as_ref(),
+            role.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                if db.constraint() == Some("user_roles_role_fkey") {
+                    UserStoreError::RoleNotFound
+                } else {
+                    UserStoreError::UserNotFound
+                }
+            }
+            e => UserStoreError::UnexpectedError(eyre!(e)),
+        })?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[tracing::instrument(name = "Removing role in PostgreSQL", skip_all)]
+    async fn remove_role(&mut self, user_id: &UserId, role: &Role) -> Result<bool, UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_roles WHERE user_id = $1 AND role = $2
+            "#,
+            user_id.as_ref(),
+            role.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[tracing::instrument(name = "Listing roles from PostgreSQL", skip_all)]
+    async fn list_roles(&self) -> Result<Vec<RoleDefinition>, UserStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT r.name, r.description,
+                COALESCE(
+                    ARRAY_AGG(rp.permission ORDER BY rp.permission)
+                        FILTER (WHERE rp.permission IS NOT NULL),
+                    '{}'
+                ) as "permissions!"
+            FROM roles r
+            LEFT JOIN role_permissions rp ON rp.role = r.name
+            GROUP BY r.name, r.description
+            ORDER BY r.name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RoleDefinition {
+                name: row.name,
+                description: row.description,
+                permissions: row.permissions,
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(name = "Listing users from PostgreSQL", skip_all)]
+    async fn list_users(
+        &self,
+        search: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<UserPage, UserStoreError> {
+        // strpos rather than LIKE, so `%` and `_` in the search match literally
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id as "id: _", email as "email: _", password_hash as "password: _",
+                requires_2fa, email_verified, two_fa_method as "two_fa_method: _", disabled,
+                password_reset_required, delete_after IS NOT NULL as "pending_deletion!"
+            FROM users
+            WHERE $1::TEXT IS NULL OR STRPOS(LOWER(email), LOWER($1)) > 0
+            ORDER BY email
+            LIMIT $2 OFFSET $3
+            "#,
+            search,
+            i64::from(limit),
+            i64::from(offset)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM users
+            WHERE $1::TEXT IS NULL OR STRPOS(LOWER(email), LOWER($1)) > 0
+            "#,
+            search
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        Ok(UserPage {
+            users,
+            total: u64::try_from(total).map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?,
         })
-    })
-    .await?
+    }
+
+    #[tracing::instrument(name = "Setting user disabled in PostgreSQL", skip_all)]
+    async fn set_disabled(
+        &mut self,
+        user_id: &UserId,
+        disabled: bool,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET disabled = $2 WHERE id = $1
+            "#,
+            user_id.as_ref(),
+            disabled
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Requiring password reset in PostgreSQL", skip_all)]
+    async fn require_password_reset(&mut self, user_id: &UserId) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET password_reset_required = TRUE WHERE id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Setting user 2FA requirement in PostgreSQL", skip_all)]
+    async fn set_requires_2fa(
+        &mut self,
+        user_id: &UserId,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET requires_2fa = $2 WHERE id = $1
+            "#,
+            user_id.as_ref(),
+            requires_2fa
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Deleting user from PostgreSQL", skip_all)]
+    async fn delete_user(&mut self, user_id: &UserId) -> Result<(), UserStoreError> {
+        // Recovery codes, passkeys and role assignments go with the user through ON DELETE CASCADE
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM users WHERE id = $1
+            "#,
+            user_id.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Scheduling user deletion in PostgreSQL", skip_all)]
+    async fn schedule_deletion(
+        &mut self,
+        user_id: &UserId,
+        grace_seconds: u64,
+    ) -> Result<i64, UserStoreError> {
+        let delete_after = sqlx::query_scalar!(
+            r#"
+            UPDATE users SET delete_after = NOW() + $2 * INTERVAL '1 second'
+            WHERE id = $1
+            RETURNING EXTRACT(EPOCH FROM delete_after)::BIGINT as "delete_after!"
+            "#,
+            user_id.as_ref(),
+            grace_seconds as f64
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        delete_after.ok_or(UserStoreError::UserNotFound)
+    }
+
+    #[tracing::instrument(name = "Cancelling user deletion in PostgreSQL", skip_all)]
+    async fn cancel_deletion(&mut self, user_id: &UserId) -> Result<bool, UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET delete_after = NULL WHERE id = $1 AND delete_after IS NOT NULL
+            "#,
+            user_id.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 1 {
+            return Ok(true);
+        }
+        // Nothing was pending; an unknown user is still an error
+        self.get_user(user_id).await.map(|_| false)
+    }
+
+    #[tracing::instrument(name = "Retrieving due user deletions from PostgreSQL", skip_all)]
+    async fn due_deletions(&self) -> Result<Vec<UserId>, UserStoreError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT id as "id: UserId" FROM users WHERE delete_after <= NOW() ORDER BY delete_after
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))
+    }
+
+    #[tracing::instrument(name = "Requesting email change in PostgreSQL", skip_all)]
+    async fn request_email_change(
+        &mut self,
+        user_id: &UserId,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET pending_email = $2
+            WHERE id = $1 AND NOT EXISTS (
+                SELECT 1 FROM users
+                WHERE lower(email) = lower($2)
+                    OR (lower(previous_email) = lower($2)
+                        AND email_changed_at > NOW() - $3 * INTERVAL '1 second')
+            )
+            "#,
+            user_id.as_ref(),
+            new_email.as_ref().expose_secret(),
+            EMAIL_CHANGE_REVERT_SECONDS as f64
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(email_change_error)?;
+
+        if result.rows_affected() == 1 {
+            return Ok(());
+        }
+        // Either the user is unknown or the new address belongs to someone
+        self.get_user(user_id).await?;
+        Err(UserStoreError::UserAlreadyExists)
+    }
+
+    #[tracing::instrument(name = "Cancelling email change in PostgreSQL", skip_all)]
+    async fn cancel_email_change(&mut self, user_id: &UserId) -> Result<bool, UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET pending_email = NULL WHERE id = $1 AND pending_email IS NOT NULL
+            "#,
+            user_id.as_ref()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?;
+
+        if result.rows_affected() == 1 {
+            return Ok(true);
+        }
+        self.get_user(user_id).await.map(|_| false)
+    }
+
+    #[tracing::instrument(name = "Confirming email change in PostgreSQL", skip_all)]
+    async fn confirm_email_change(
+        &mut self,
+        new_email: &Email,
+    ) -> Result<(UserId, Email), UserStoreError> {
+        // A change made while an earlier one can be undone keeps the earlier one's address and
+        // window, so chaining changes can't shake off the original owner
+        let row = sqlx::query!(
+            r#"
+            UPDATE users
+            SET email = pending_email, pending_email = NULL, email_verified = TRUE,
+                previous_email = CASE
+                    WHEN email_changed_at > NOW() - $2 * INTERVAL '1 second' THEN previous_email
+                    ELSE email
+                END,
+                email_changed_at = CASE
+                    WHEN email_changed_at > NOW() - $2 * INTERVAL '1 second' THEN email_changed_at
+                    ELSE NOW()
+                END
+            WHERE lower(pending_email) = lower($1)
+            RETURNING id as "id: UserId", previous_email as "previous_email!"
+            "#,
+            new_email.as_ref().expose_secret(),
+            EMAIL_CHANGE_REVERT_SECONDS as f64
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(email_change_error)?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        let previous_email = Email::parse(Secret::new(row.previous_email))
+            .map_err(UserStoreError::UnexpectedError)?;
+        Ok((row.id, previous_email))
+    }
+
+    #[tracing::instrument(name = "Reverting email change in PostgreSQL", skip_all)]
+    async fn revert_email_change(
+        &mut self,
+        previous_email: &Email,
+    ) -> Result<UserId, UserStoreError> {
+        // Following the link proves the old address is still its owner's
+        sqlx::query_scalar!(
+            r#"
+            UPDATE users
+            SET email = previous_email, previous_email = NULL, email_changed_at = NULL,
+                pending_email = NULL, email_verified = TRUE
+            WHERE lower(previous_email) = lower($1)
+                AND email_changed_at > NOW() - $2 * INTERVAL '1 second'
+            RETURNING id as "id: UserId"
+            "#,
+            previous_email.as_ref().expose_secret(),
+            EMAIL_CHANGE_REVERT_SECONDS as f64
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(email_change_error)?
+        .ok_or(UserStoreError::UserNotFound)
+    }
 }
 
-#[tracing::instrument(name = "Computing password hash", skip_all)]
-async fn compute_password_hash(password: Secret<String>) -> Result<String> {
-    let current_span: tracing::Span = tracing::Span::current();
-
-    let password_hash = tokio::task::spawn_blocking(move || -> Result<String> {
-        current_span.in_scope(|| {
-            let find_hash = password.expose_secret();
-            let salt = SaltString::generate(&mut rand::thread_rng());
-            let params = Params::new(15000, 2, 1, None)
-                .map_err(|e| eyre!("invalid argon2 params: {}", e))?;
-            let argon = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
-            let ph = argon
-                .hash_password(find_hash.as_bytes(), &salt)
-                .map_err(|e| eyre!("failed to hash password: {}", e))?;
-            Ok(ph.to_string())
-        })
-    })
-    .await??;
+/// The new address was taken in the meantime, by a signup or another user's pending change.
+fn email_change_error(e: sqlx::Error) -> UserStoreError {
+    match e.as_database_error() {
+        Some(db_error) if db_error.is_unique_violation() => UserStoreError::UserAlreadyExists,
+        _ => UserStoreError::UnexpectedError(eyre!(e)),
+    }
+}
 
-    Ok(password_hash)
+fn decrypt_totp_secret(encrypted: &[u8]) -> Result<TotpSecret, UserStoreError> {
+    let bytes = decrypt(&TOTP_ENCRYPTION_KEY, encrypted)
+        .wrap_err("failed to decrypt TOTP secret")
+        .map_err(UserStoreError::UnexpectedError)?;
+    TotpSecret::from_bytes(bytes.expose_secret().clone()).map_err(UserStoreError::UnexpectedError)
 }
@@ -1,12 +1,11 @@
-use color_eyre::eyre::eyre;
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Report};
 use redis::{Commands, Connection};
+use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::{
-    domain::{BannedTokenStore, BannedTokenStoreError},
-    utils::TOKEN_TTL_SECONDS,
-};
+use crate::domain::{BannedTokenStore, BannedTokenStoreError, PersonalDataExporter, UserId};
 
 pub struct RedisBannedTokenStore {
     conn: Arc<RwLock<Connection>>,
@@ -18,18 +17,40 @@ impl RedisBannedTokenStore {
     }
 }
 
+#[async_trait::async_trait]
+impl PersonalDataExporter for RedisBannedTokenStore {
+    fn export_section(&self) -> &'static str {
+        "tokenBans"
+    }
+
+    // Bans and token generations describe tokens, not the person holding them
+    async fn export_personal_data(&self, _user_id: &UserId) -> Result<Option<Value>, Report> {
+        Ok(None)
+    }
+}
+
 #[async_trait::async_trait]
 impl BannedTokenStore for RedisBannedTokenStore {
     #[tracing::instrument(name = "Add Banned Token", skip_all)]
-    async fn add_banned_token(&mut self, token: &str) -> Result<(), BannedTokenStoreError> {
-        let key = get_key(token);
+    async fn add_banned_token(
+        &mut self,
+        token_id: &str,
+        expires_at: usize,
+    ) -> Result<(), BannedTokenStoreError> {
+        let key = get_key(token_id);
 
-        let ttl = u64::try_from(TOKEN_TTL_SECONDS).map_err(|e| {
+        // Keep the ban only for the token's remaining lifetime
+        let now = u64::try_from(Utc::now().timestamp()).map_err(|e| {
             BannedTokenStoreError::UnexpectedError(eyre!(
-                "tfailed to cast TOKEN_TTL_SECONDS to u64: {}",
+                "failed to cast current time to u64: {}",
                 e
             ))
         })?;
+        let ttl = (expires_at as u64).saturating_sub(now);
+        if ttl == 0 {
+            // Already expired, nothing left to ban
+            return Ok(());
+        }
 
         let mut conn = self.conn.write().await;
 
@@ -46,8 +67,8 @@ impl BannedTokenStore for RedisBannedTokenStore {
     }
 
     #[tracing::instrument(name = "Is Token Banned", skip_all)]
-    async fn is_token_banned(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
-        let key = get_key(token);
+    async fn is_token_banned(&self, token_id: &str) -> Result<bool, BannedTokenStoreError> {
+        let key = get_key(token_id);
 
         let mut conn = self.conn.write().await;
 
@@ -60,11 +81,74 @@ impl BannedTokenStore for RedisBannedTokenStore {
 
         Ok(exists)
     }
+
+    #[tracing::instrument(name = "Get Token Version", skip_all)]
+    async fn get_token_version(&self, user_id: &UserId) -> Result<u64, BannedTokenStoreError> {
+        let key = get_version_key(user_id);
+
+        let mut conn = self.conn.write().await;
+
+        let version: Option<u64> = conn.get(key).map_err(|e: redis::RedisError| {
+            BannedTokenStoreError::UnexpectedError(eyre!(
+                "failed to get token version from Redis: {}",
+                e
+            ))
+        })?;
+
+        Ok(version.unwrap_or_default())
+    }
+
+    #[tracing::instrument(name = "Increment Token Version", skip_all)]
+    async fn increment_token_version(
+        &mut self,
+        user_id: &UserId,
+    ) -> Result<u64, BannedTokenStoreError> {
+        let key = get_version_key(user_id);
+
+        let mut conn = self.conn.write().await;
+
+        // Kept without a TTL: once it expired, old tokens would become valid again
+        let version: u64 = conn.incr(key, 1).map_err(|e: redis::RedisError| {
+            BannedTokenStoreError::UnexpectedError(eyre!(
+                "failed to increment token version in Redis: {}",
+                e
+            ))
+        })?;
+
+        Ok(version)
+    }
+
+    #[tracing::instrument(name = "Expire Token Version", skip_all)]
+    async fn expire_token_version(
+        &mut self,
+        user_id: &UserId,
+        ttl_seconds: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let key = get_version_key(user_id);
+        let ttl = i64::try_from(ttl_seconds)
+            .map_err(|e| BannedTokenStoreError::UnexpectedError(eyre!(e)))?;
+
+        let mut conn = self.conn.write().await;
+
+        let _: bool = conn.expire(key, ttl).map_err(|e: redis::RedisError| {
+            BannedTokenStoreError::UnexpectedError(eyre!(
+                "failed to set token version TTL in Redis: {}",
+                e
+            ))
+        })?;
+
+        Ok(())
+    }
 }
 
 // We are using a key prefix to prevent collisions and organize data!
 const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+const TOKEN_VERSION_KEY_PREFIX: &str = "token_version:";
+
+fn get_key(token_id: &str) -> String {
+    format!("{}{}", BANNED_TOKEN_KEY_PREFIX, token_id)
+}
 
-fn get_key(token: &str) -> String {
-    format!("{}{}", BANNED_TOKEN_KEY_PREFIX, token)
+fn get_version_key(user_id: &UserId) -> String {
+    format!("{}{}", TOKEN_VERSION_KEY_PREFIX, user_id)
 }
@@ -0,0 +1,110 @@
+use color_eyre::eyre::{Context, Report};
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{
+        Email, MagicLinkStore, MagicLinkStoreError, OneTimeToken, PersonalDataExporter, UserId,
+    },
+    utils::MAGIC_LINK_TTL_SECONDS,
+};
+
+pub struct RedisMagicLinkStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisMagicLinkStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for RedisMagicLinkStore {
+    fn export_section(&self) -> &'static str {
+        "magicLinks"
+    }
+
+    // Only pending login links, which are secrets
+    async fn export_personal_data(&self, _user_id: &UserId) -> Result<Option<Value>, Report> {
+        Ok(None)
+    }
+}
+
+#[async_trait::async_trait]
+impl MagicLinkStore for RedisMagicLinkStore {
+    #[tracing::instrument(name = "Add Magic Link", skip_all)]
+    async fn add_link(
+        &mut self,
+        token: &OneTimeToken,
+        email: &Email,
+        nonce: &OneTimeToken,
+    ) -> Result<(), MagicLinkStoreError> {
+        let tuple = MagicLinkTuple(
+            email.as_ref().expose_secret().to_owned(),
+            nonce.as_ref().expose_secret().to_owned(),
+        );
+        let serialized = serde_json::to_string(&tuple)
+            .wrap_err("failed to serialize magic link tuple")
+            .map_err(MagicLinkStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+        let _: redis::Value = conn
+            .set_ex(get_key(token), serialized, MAGIC_LINK_TTL_SECONDS)
+            .wrap_err("failed to set magic link in Redis")
+            .map_err(MagicLinkStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Consume Magic Link", skip_all)]
+    async fn consume_link(
+        &mut self,
+        token: &OneTimeToken,
+        nonce: &OneTimeToken,
+    ) -> Result<Email, MagicLinkStoreError> {
+        let key = get_key(token);
+        let mut conn = self.conn.write().await;
+        let value: Option<String> = conn
+            .get(&key)
+            .wrap_err("failed to get magic link from Redis")
+            .map_err(MagicLinkStoreError::UnexpectedError)?;
+        let value = value.ok_or(MagicLinkStoreError::LinkNotFound)?;
+
+        let tuple: MagicLinkTuple = serde_json::from_str(&value)
+            .wrap_err("failed to deserialize magic link tuple")
+            .map_err(MagicLinkStoreError::UnexpectedError)?;
+        if tuple.1 != *nonce.as_ref().expose_secret() {
+            return Err(MagicLinkStoreError::NonceMismatch);
+        }
+
+        // Only the request whose DEL removes the key may log in, so a link can't be used twice
+        let deleted: i32 = conn
+            .del(&key)
+            .wrap_err("failed to delete magic link from Redis")
+            .map_err(MagicLinkStoreError::UnexpectedError)?;
+        if deleted == 0 {
+            return Err(MagicLinkStoreError::LinkNotFound);
+        }
+
+        Email::parse(Secret::new(tuple.0))
+            .wrap_err("failed to parse magic link email")
+            .map_err(MagicLinkStoreError::UnexpectedError)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MagicLinkTuple(pub String, pub String);
+
+const MAGIC_LINK_KEY_PREFIX: &str = "magic_link:";
+
+fn get_key(token: &OneTimeToken) -> String {
+    format!(
+        "{}{}",
+        MAGIC_LINK_KEY_PREFIX,
+        token.as_ref().expose_secret()
+    )
+}
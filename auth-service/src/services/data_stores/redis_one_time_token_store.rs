@@ -0,0 +1,83 @@
+use color_eyre::eyre::{Context, Report};
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    Email, OneTimeToken, OneTimeTokenStore, OneTimeTokenStoreError, PersonalDataExporter,
+    TokenPurpose, UserId,
+};
+
+pub struct RedisOneTimeTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisOneTimeTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for RedisOneTimeTokenStore {
+    fn export_section(&self) -> &'static str {
+        "oneTimeTokens"
+    }
+
+    // Only pending emailed tokens, which are secrets
+    async fn export_personal_data(&self, _user_id: &UserId) -> Result<Option<Value>, Report> {
+        Ok(None)
+    }
+}
+
+#[async_trait::async_trait]
+impl OneTimeTokenStore for RedisOneTimeTokenStore {
+    #[tracing::instrument(name = "Add One-Time Token", skip_all)]
+    async fn add_token(
+        &mut self,
+        purpose: TokenPurpose,
+        token: &OneTimeToken,
+        email: &Email,
+    ) -> Result<(), OneTimeTokenStoreError> {
+        let key = get_key(purpose, token);
+        let mut conn = self.conn.write().await;
+        let _: redis::Value = conn
+            .set_ex(key, email.as_ref().expose_secret(), purpose.ttl_seconds())
+            .wrap_err("failed to set one-time token in Redis")
+            .map_err(OneTimeTokenStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Consume One-Time Token", skip_all)]
+    async fn consume_token(
+        &mut self,
+        purpose: TokenPurpose,
+        token: &OneTimeToken,
+    ) -> Result<Email, OneTimeTokenStoreError> {
+        let key = get_key(purpose, token);
+        let mut conn = self.conn.write().await;
+        // GETDEL reads and removes in one step, so two concurrent redemptions cannot both succeed
+        let value: Option<String> = conn
+            .get_del(key)
+            .wrap_err("failed to consume one-time token in Redis")
+            .map_err(OneTimeTokenStoreError::UnexpectedError)?;
+        let value = value.ok_or(OneTimeTokenStoreError::TokenNotFound)?;
+
+        Email::parse(Secret::new(value))
+            .wrap_err("failed to parse one-time token email")
+            .map_err(OneTimeTokenStoreError::UnexpectedError)
+    }
+}
+
+const ONE_TIME_TOKEN_KEY_PREFIX: &str = "one_time_token:";
+
+fn get_key(purpose: TokenPurpose, token: &OneTimeToken) -> String {
+    format!(
+        "{}{}:{}",
+        ONE_TIME_TOKEN_KEY_PREFIX,
+        purpose.as_str(),
+        token.as_ref().expose_secret()
+    )
+}
@@ -0,0 +1,81 @@
+use chrono::Utc;
+use color_eyre::eyre::{Context, Report};
+use redis::{Commands, Connection};
+use serde_json::Value;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::domain::{PersonalDataExporter, RateLimit, RateLimitStore, RateLimitStoreError, UserId};
+
+pub struct RedisRateLimitStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for RedisRateLimitStore {
+    fn export_section(&self) -> &'static str {
+        "rateLimits"
+    }
+
+    // Attempt counters only live as long as their window
+    async fn export_personal_data(&self, _user_id: &UserId) -> Result<Option<Value>, Report> {
+        Ok(None)
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    #[tracing::instrument(name = "Record Rate Limited Attempt", skip_all)]
+    async fn record_attempt(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<Option<Duration>, RateLimitStoreError> {
+        let key = get_key(key);
+        let now = Utc::now().timestamp_millis().unsigned_abs();
+        let window = u64::try_from(limit.window.as_millis()).unwrap_or(u64::MAX);
+        let attempt = Uuid::new_v4().to_string();
+
+        // A sliding window log: one entry per attempt, scored by its time in milliseconds.
+        // Entries that left the window are dropped before counting.
+        let mut conn = self.conn.write().await;
+        let (attempts, oldest): (u32, Vec<(String, u64)>) = redis::pipe()
+            .atomic()
+            .zrembyscore(&key, "-inf", now.saturating_sub(window))
+            .ignore()
+            .zadd(&key, &attempt, now)
+            .ignore()
+            .zcard(&key)
+            .zrange_withscores(&key, 0, 0)
+            .pexpire(&key, i64::try_from(window).unwrap_or(i64::MAX))
+            .ignore()
+            .query(&mut *conn)
+            .wrap_err("failed to record attempt in Redis")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+
+        if attempts <= limit.max_attempts {
+            return Ok(None);
+        }
+
+        let _: i32 = conn
+            .zrem(&key, &attempt)
+            .wrap_err("failed to discard rejected attempt from Redis")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+        // The next attempt fits once the oldest one leaves the window
+        let retry_at = oldest.first().map_or(now + window, |(_, at)| at + window);
+        Ok(Some(Duration::from_millis(retry_at.saturating_sub(now))))
+    }
+}
+
+const RATE_LIMIT_KEY_PREFIX: &str = "rate_limit:";
+
+fn get_key(key: &str) -> String {
+    format!("{}{}", RATE_LIMIT_KEY_PREFIX, key)
+}
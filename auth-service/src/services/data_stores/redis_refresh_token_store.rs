@@ -0,0 +1,260 @@
+use color_eyre::eyre::{eyre, Context, Report};
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{
+        PersonalDataExporter, RefreshToken, RefreshTokenFamily, RefreshTokenStore,
+        RefreshTokenStoreError, UserId,
+    },
+    utils::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisRefreshTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRefreshTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for RedisRefreshTokenStore {
+    fn export_section(&self) -> &'static str {
+        "refreshTokens"
+    }
+
+    // Refresh token families are the user's sessions, which the session store exports
+    async fn export_personal_data(&self, _user_id: &UserId) -> Result<Option<Value>, Report> {
+        Ok(None)
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for RedisRefreshTokenStore {
+    #[tracing::instrument(name = "Add Refresh Token", skip_all)]
+    async fn add_token(
+        &mut self,
+        token: &RefreshToken,
+        family: &RefreshTokenFamily,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let ttl = token_ttl()?;
+        let serialized = serialize_family(family)?;
+
+        let mut conn = self.conn.write().await;
+
+        // The token record points at its family; the family key tracks which token is current.
+        let _: redis::Value = conn
+            .set_ex(get_token_key(token), serialized, ttl)
+            .wrap_err("failed to set refresh token in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let _: redis::Value = conn
+            .set_ex(
+                get_family_key(&family.id),
+                token.as_ref().expose_secret(),
+                ttl,
+            )
+            .wrap_err("failed to set refresh token family in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        // Index families by user so they can all be revoked at once
+        let user_key = get_user_key(&family.user_id);
+        let _: i32 = conn
+            .sadd(&user_key, &family.id)
+            .wrap_err("failed to index refresh token family in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let _: bool = conn
+            .expire(&user_key, REFRESH_TOKEN_TTL_SECONDS)
+            .wrap_err("failed to set refresh token family index TTL in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Get Refresh Token Family", skip_all)]
+    async fn get_family(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenFamily, RefreshTokenStoreError> {
+        let mut conn = self.conn.write().await;
+        let value: Option<String> = conn
+            .get(get_token_key(token))
+            .wrap_err("failed to get refresh token from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let value = value.ok_or(RefreshTokenStoreError::TokenNotFound)?;
+
+        let tuple: RefreshTokenTuple = serde_json::from_str(&value)
+            .wrap_err("failed to deserialize refresh token tuple")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        Ok(RefreshTokenFamily {
+            id: tuple.0,
+            user_id: tuple.1,
+        })
+    }
+
+    #[tracing::instrument(name = "Get Current Refresh Token", skip_all)]
+    async fn get_current_token(
+        &self,
+        family_id: &str,
+    ) -> Result<RefreshToken, RefreshTokenStoreError> {
+        let mut conn = self.conn.write().await;
+        let value: Option<String> = conn
+            .get(get_family_key(family_id))
+            .wrap_err("failed to get refresh token family from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let value = value.ok_or(RefreshTokenStoreError::TokenNotFound)?;
+
+        RefreshToken::parse(Secret::new(value)).map_err(RefreshTokenStoreError::UnexpectedError)
+    }
+
+    /// Compare-and-swap on the family's current token: the family key is watched, so when two
+    /// refreshes race with the same token the slower one sees the swap and is treated as reuse.
+    #[tracing::instrument(name = "Rotate Refresh Token", skip_all)]
+    async fn rotate(
+        &mut self,
+        presented: &RefreshToken,
+        replacement: &RefreshToken,
+    ) -> Result<RefreshTokenFamily, RefreshTokenStoreError> {
+        let family = self.get_family(presented).await?;
+        let ttl = token_ttl()?;
+        let serialized = serialize_family(&family)?;
+        let family_key = get_family_key(&family.id);
+
+        let rotated = {
+            let mut conn = self.conn.write().await;
+            redis::transaction(&mut *conn, &[&family_key], |conn, pipe| {
+                let current: Option<String> = conn.get(&family_key)?;
+                if current.as_deref() != Some(presented.as_ref().expose_secret().as_str()) {
+                    return Ok(Some(false));
+                }
+                // None when the family changed since WATCH, which makes the transaction retry
+                let swapped: Option<()> = pipe
+                    .set_ex(get_token_key(replacement), &serialized, ttl)
+                    .ignore()
+                    .set_ex(&family_key, replacement.as_ref().expose_secret(), ttl)
+                    .ignore()
+                    .expire(get_user_key(&family.user_id), REFRESH_TOKEN_TTL_SECONDS)
+                    .ignore()
+                    .query(conn)?;
+                Ok(swapped.map(|()| true))
+            })
+            .wrap_err("failed to rotate refresh token in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?
+        };
+
+        if !rotated {
+            self.revoke_family(&family.id).await?;
+            return Err(RefreshTokenStoreError::TokenReused);
+        }
+        Ok(family)
+    }
+
+    #[tracing::instrument(name = "Revoke Refresh Token Family", skip_all)]
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        let mut conn = self.conn.write().await;
+        // Individual token records are left to expire; without a family they can no longer rotate.
+        let _: i32 = conn
+            .del(get_family_key(family_id))
+            .wrap_err("failed to delete refresh token family from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoke All Refresh Token Families", skip_all)]
+    async fn revoke_all_families(
+        &mut self,
+        user_id: &UserId,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let user_key = get_user_key(user_id);
+        let mut conn = self.conn.write().await;
+        let family_ids: Vec<String> = conn
+            .smembers(&user_key)
+            .wrap_err("failed to get refresh token families from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        let mut keys: Vec<String> = family_ids.iter().map(|id| get_family_key(id)).collect();
+        keys.push(user_key);
+        let _: i32 = conn
+            .del(keys)
+            .wrap_err("failed to delete refresh token families from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoke Other Refresh Token Families", skip_all)]
+    async fn revoke_other_families(
+        &mut self,
+        user_id: &UserId,
+        keep_family_id: &str,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let user_key = get_user_key(user_id);
+        let mut conn = self.conn.write().await;
+        let family_ids: Vec<String> = conn
+            .smembers(&user_key)
+            .wrap_err("failed to get refresh token families from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let revoked: Vec<String> = family_ids
+            .into_iter()
+            .filter(|id| id != keep_family_id)
+            .collect();
+        if revoked.is_empty() {
+            return Ok(());
+        }
+
+        let keys: Vec<String> = revoked.iter().map(|id| get_family_key(id)).collect();
+        let _: i32 = conn
+            .del(keys)
+            .wrap_err("failed to delete refresh token families from Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        let _: i32 = conn
+            .srem(&user_key, revoked)
+            .wrap_err("failed to unindex refresh token families in Redis")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RefreshTokenTuple(pub String, pub UserId);
+
+fn token_ttl() -> Result<u64, RefreshTokenStoreError> {
+    u64::try_from(REFRESH_TOKEN_TTL_SECONDS).map_err(|e| {
+        RefreshTokenStoreError::UnexpectedError(eyre!(
+            "failed to cast REFRESH_TOKEN_TTL_SECONDS to u64: {}",
+            e
+        ))
+    })
+}
+
+fn serialize_family(family: &RefreshTokenFamily) -> Result<String, RefreshTokenStoreError> {
+    let tuple = RefreshTokenTuple(family.id.clone(), family.user_id);
+    serde_json::to_string(&tuple)
+        .wrap_err("failed to serialize refresh token tuple")
+        .map_err(RefreshTokenStoreError::UnexpectedError)
+}
+
+const REFRESH_TOKEN_KEY_PREFIX: &str = "refresh_token:";
+const REFRESH_TOKEN_FAMILY_KEY_PREFIX: &str = "refresh_token_family:";
+const REFRESH_TOKEN_USER_KEY_PREFIX: &str = "refresh_token_user:";
+
+fn get_token_key(token: &RefreshToken) -> String {
+    format!(
+        "{}{}",
+        REFRESH_TOKEN_KEY_PREFIX,
+        token.as_ref().expose_secret()
+    )
+}
+
+fn get_family_key(family_id: &str) -> String {
+    format!("{}{}", REFRESH_TOKEN_FAMILY_KEY_PREFIX, family_id)
+}
+
+fn get_user_key(user_id: &UserId) -> String {
+    format!("{}{}", REFRESH_TOKEN_USER_KEY_PREFIX, user_id)
+}
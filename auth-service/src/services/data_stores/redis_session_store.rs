@@ -0,0 +1,222 @@
+use color_eyre::eyre::{Context, Report};
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{ClientInfo, PersonalDataExporter, Session, SessionStore, SessionStoreError, UserId},
+    utils::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisSessionStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisSessionStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonalDataExporter for RedisSessionStore {
+    fn export_section(&self) -> &'static str {
+        "sessions"
+    }
+
+    #[tracing::instrument(name = "Export Sessions", skip_all)]
+    async fn export_personal_data(&self, user_id: &UserId) -> Result<Option<Value>, Report> {
+        let sessions: Vec<Value> = self
+            .get_sessions(user_id)
+            .await?
+            .into_iter()
+            .map(|session| {
+                json!({
+                    "id": session.id,
+                    "device": session.device,
+                    "ip": session.ip,
+                    "userAgent": session.user_agent,
+                    "createdAt": session.created_at,
+                    "lastSeenAt": session.last_seen_at,
+                })
+            })
+            .collect();
+        Ok(Some(Value::Array(sessions)))
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for RedisSessionStore {
+    #[tracing::instrument(name = "Add Session", skip_all)]
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError> {
+        let mut conn = self.conn.write().await;
+        save_session(&mut conn, &session)?;
+
+        // Index sessions by user so they can be listed
+        let user_key = get_user_key(&session.user_id);
+        let _: i32 = conn
+            .sadd(&user_key, &session.id)
+            .wrap_err("failed to index session in Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+        let _: bool = conn
+            .expire(&user_key, REFRESH_TOKEN_TTL_SECONDS)
+            .wrap_err("failed to set session index TTL in Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Get Session", skip_all)]
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError> {
+        let mut conn = self.conn.write().await;
+        load_session(&mut conn, id)?.ok_or(SessionStoreError::SessionNotFound)
+    }
+
+    #[tracing::instrument(name = "Get Sessions", skip_all)]
+    async fn get_sessions(&self, user_id: &UserId) -> Result<Vec<Session>, SessionStoreError> {
+        let user_key = get_user_key(user_id);
+        let mut conn = self.conn.write().await;
+        let ids: Vec<String> = conn
+            .smembers(&user_key)
+            .wrap_err("failed to get sessions from Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+
+        let mut sessions = Vec::with_capacity(ids.len());
+        let mut expired = Vec::new();
+        for id in ids {
+            match load_session(&mut conn, &id)? {
+                Some(session) => sessions.push(session),
+                None => expired.push(id),
+            }
+        }
+        if !expired.is_empty() {
+            let _: i32 = conn
+                .srem(&user_key, expired)
+                .wrap_err("failed to unindex expired sessions in Redis")
+                .map_err(SessionStoreError::UnexpectedError)?;
+        }
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_seen_at));
+        Ok(sessions)
+    }
+
+    #[tracing::instrument(name = "Touch Session", skip_all)]
+    async fn touch_session(
+        &mut self,
+        id: &str,
+        client: &ClientInfo,
+    ) -> Result<(), SessionStoreError> {
+        let mut conn = self.conn.write().await;
+        let mut session = load_session(&mut conn, id)?.ok_or(SessionStoreError::SessionNotFound)?;
+        session.touch(client);
+        // Saving again also extends the TTL, in step with the refresh token family
+        save_session(&mut conn, &session)
+    }
+
+    #[tracing::instrument(name = "Remove Session", skip_all)]
+    async fn remove_session(&mut self, id: &str) -> Result<(), SessionStoreError> {
+        let mut conn = self.conn.write().await;
+        let session = load_session(&mut conn, id)?.ok_or(SessionStoreError::SessionNotFound)?;
+
+        let _: i32 = conn
+            .del(get_session_key(id))
+            .wrap_err("failed to delete session from Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+        let _: i32 = conn
+            .srem(get_user_key(&session.user_id), id)
+            .wrap_err("failed to unindex session in Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Remove All Sessions", skip_all)]
+    async fn remove_all_sessions(&mut self, user_id: &UserId) -> Result<(), SessionStoreError> {
+        let user_key = get_user_key(user_id);
+        let mut conn = self.conn.write().await;
+        let ids: Vec<String> = conn
+            .smembers(&user_key)
+            .wrap_err("failed to get sessions from Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+
+        let mut keys: Vec<String> = ids.iter().map(|id| get_session_key(id)).collect();
+        keys.push(user_key);
+        let _: i32 = conn
+            .del(keys)
+            .wrap_err("failed to delete sessions from Redis")
+            .map_err(SessionStoreError::UnexpectedError)?;
+        Ok(())
+    }
+}
+
+/// Session as stored in Redis.
+#[derive(Serialize, Deserialize)]
+struct SessionRecord {
+    user_id: UserId,
+    device: String,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    created_at: i64,
+    last_seen_at: i64,
+}
+
+fn save_session(conn: &mut Connection, session: &Session) -> Result<(), SessionStoreError> {
+    let _: redis::Value = conn
+        .set_ex(
+            get_session_key(&session.id),
+            serialize_session(session)?,
+            REFRESH_TOKEN_TTL_SECONDS.unsigned_abs(),
+        )
+        .wrap_err("failed to set session in Redis")
+        .map_err(SessionStoreError::UnexpectedError)?;
+    Ok(())
+}
+
+fn serialize_session(session: &Session) -> Result<String, SessionStoreError> {
+    let record = SessionRecord {
+        user_id: session.user_id,
+        device: session.device.clone(),
+        ip: session.ip.clone(),
+        user_agent: session.user_agent.clone(),
+        created_at: session.created_at,
+        last_seen_at: session.last_seen_at,
+    };
+    serde_json::to_string(&record)
+        .wrap_err("failed to serialize session")
+        .map_err(SessionStoreError::UnexpectedError)
+}
+
+fn load_session(conn: &mut Connection, id: &str) -> Result<Option<Session>, SessionStoreError> {
+    let value: Option<String> = conn
+        .get(get_session_key(id))
+        .wrap_err("failed to get session from Redis")
+        .map_err(SessionStoreError::UnexpectedError)?;
+    let Some(value) = value else {
+        return Ok(None);
+    };
+
+    let record: SessionRecord = serde_json::from_str(&value)
+        .wrap_err("failed to deserialize session")
+        .map_err(SessionStoreError::UnexpectedError)?;
+
+    Ok(Some(Session {
+        id: id.to_owned(),
+        user_id: record.user_id,
+        device: record.device,
+        ip: record.ip,
+        user_agent: record.user_agent,
+        created_at: record.created_at,
+        last_seen_at: record.last_seen_at,
+    }))
+}
+
+const SESSION_KEY_PREFIX: &str = "session:";
+const SESSION_USER_KEY_PREFIX: &str = "session_user:";
+
+fn get_session_key(id: &str) -> String {
+    format!("{}{}", SESSION_KEY_PREFIX, id)
+}
+
+fn get_user_key(user_id: &UserId) -> String {
+    format!("{}{}", SESSION_USER_KEY_PREFIX, user_id)
+}
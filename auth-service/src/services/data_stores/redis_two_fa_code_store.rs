@@ -1,12 +1,12 @@
-use color_eyre::eyre::Context;
+use color_eyre::eyre::{Context, Report};
 use redis::{Commands, Connection};
-use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::domain::{
-    Email, TwoFACodeStoreError, {LoginAttemptId, TwoFACode, TwoFACodeStore},
+    PersonalDataExporter, TwoFACodeStoreError, UserId, {LoginAttemptId, TwoFACode, TwoFACodeStore},
 };
 
 pub struct RedisTwoFACodeStore {
@@ -19,17 +19,29 @@ impl RedisTwoFACodeStore {
     }
 }
 
+#[async_trait::async_trait]
+impl PersonalDataExporter for RedisTwoFACodeStore {
+    fn export_section(&self) -> &'static str {
+        "twoFACodes"
+    }
+
+    // A pending 2FA code is a short-lived secret
+    async fn export_personal_data(&self, _user_id: &UserId) -> Result<Option<Value>, Report> {
+        Ok(None)
+    }
+}
+
 #[async_trait::async_trait]
 impl TwoFACodeStore for RedisTwoFACodeStore {
     #[tracing::instrument(name = "2FA Add Code", skip_all)]
     async fn add_code(
         &mut self,
-        email: Email,
+        user_id: UserId,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError> {
         // 1. Create a new key using the get_key helper function.
-        let key = get_key(&email);
+        let key = get_key(&user_id);
         // 2. Create a TwoFATuple instance.
         let tuple = TwoFATuple(
             login_attempt_id.as_ref().to_string(),
@@ -53,9 +65,9 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
     }
 
     #[tracing::instrument(name = "2FA Remove Code", skip_all)]
-    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+    async fn remove_code(&mut self, user_id: &UserId) -> Result<(), TwoFACodeStoreError> {
         // 1. Create a new key using the get_key helper function.
-        let key = get_key(&email);
+        let key = get_key(user_id);
         // 2. Call the del command on the Redis connection to delete the 2FA code entry.
         // Return TwoFACodeStoreError::UnexpectedError if the operation fails.
         let mut conn = self.conn.write().await;
@@ -67,10 +79,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
             Ok(())
         } else {
             Err(TwoFACodeStoreError::UnexpectedError(
-                color_eyre::eyre::eyre!(
-                    "Nothing found, nothing deleted for {}",
-                    email.as_ref().expose_secret()
-                ),
+                color_eyre::eyre::eyre!("Nothing found, nothing deleted for {}", user_id),
             ))
         }
     }
@@ -78,10 +87,10 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
     #[tracing::instrument(name = "2FA Get Code", skip_all)]
     async fn get_code(
         &self,
-        email: &Email,
+        user_id: &UserId,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
         // 1. Create a new key using the get_key helper function.
-        let key = get_key(&email);
+        let key = get_key(user_id);
         // 2. Call the get command on the Redis connection to get the value stored for the key.
         // Return TwoFACodeStoreError::LoginAttemptIdNotFound if the operation fails.
         // If the operation succeeds, call serde_json::from_str to parse the JSON string into a TwoFATuple.
@@ -101,8 +110,8 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
         let login_attempt_id: LoginAttemptId = LoginAttemptId::parse(&tuple.0)
             .wrap_err("failed to parse login attempt ID")
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
-        let two_fa_code: TwoFACode = TwoFACode::parse(&tuple.1)
-            .map_err(|e| TwoFACodeStoreError::UnexpectedError(e.into()))?;
+        let two_fa_code: TwoFACode =
+            TwoFACode::parse(&tuple.1).map_err(TwoFACodeStoreError::UnexpectedError)?;
 
         Ok((login_attempt_id, two_fa_code))
     }
@@ -114,6 +123,6 @@ struct TwoFATuple(pub String, pub String);
 const TEN_MINUTES_IN_SECONDS: u64 = 600;
 const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
 
-fn get_key(email: &Email) -> String {
-    format!("{}{}", TWO_FA_CODE_PREFIX, email.as_ref().expose_secret())
+fn get_key(user_id: &UserId) -> String {
+    format!("{}{}", TWO_FA_CODE_PREFIX, user_id)
 }
@@ -1,11 +1,45 @@
 use crate::domain::{Email, EmailClient};
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
 
-pub struct MockEmailClient;
+#[derive(Debug, Clone)]
+pub struct SentEmail {
+    pub recipient: Email,
+    pub subject: String,
+    pub content: String,
+}
+
+#[derive(Default)]
+pub struct MockEmailClient {
+    sent: Mutex<Vec<SentEmail>>,
+    failing: AtomicBool,
+}
+
+impl MockEmailClient {
+    /// Every email "sent" so far, oldest first; lets tests pick links and codes out of them.
+    pub fn sent_emails(&self) -> Vec<SentEmail> {
+        self.sent
+            .lock()
+            .map(|sent| sent.clone())
+            .unwrap_or_default()
+    }
+
+    /// Make sends fail, as when the email provider is down, until switched back.
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, Ordering::SeqCst);
+    }
+}
 
 #[async_trait::async_trait]
 impl EmailClient for MockEmailClient {
     async fn send_email(&self, recipient: &Email, subject: &str, content: &str) -> Result<()> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(eyre!("mock email client is failing"));
+        }
+
         // Our mock email client will simply log the recipient, subject, and content to standard output
         tracing::debug!(
             "Sending email to {:?} with subject: {} and content: {}",
@@ -14,6 +48,15 @@ impl EmailClient for MockEmailClient {
             content
         );
 
+        self.sent
+            .lock()
+            .map_err(|_| eyre!("mock email client lock poisoned"))?
+            .push(SentEmail {
+                recipient: recipient.clone(),
+                subject: subject.to_owned(),
+                content: content.to_owned(),
+            });
+
         Ok(())
     }
 }
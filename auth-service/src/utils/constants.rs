@@ -1,7 +1,16 @@
+use super::jwt_keys::JwtKeyring;
+use crate::domain::{AdminKey, ApiClients, TrustedProxies, UnverifiedLoginPolicy};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::{eyre, Context, Result};
 use dotenvy::dotenv;
 use lazy_static::lazy_static;
 use secrecy::Secret;
-use std::env as std_env;
+use std::{
+    env as std_env,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
 
 pub mod prod {
     pub const APP_ADDRESS: &str = "0.0.0.0:3000";
@@ -26,20 +35,86 @@ pub mod test {
 
 // Define a lazily evaluated static. lazy_static is needed because std_env::var is not a const function.
 lazy_static! {
-    pub static ref JWT_SECRET: String = set_token();
+    pub static ref JWT_KEYRING: RwLock<Arc<JwtKeyring>> = RwLock::new(Arc::new(
+        load_jwt_keyring().expect("Failed to load JWT keyring")
+    ));
     pub static ref POSTGRES_PASSWORD: String = set_postgres_password();
     pub static ref DATABASE_URL: Secret<String> = set_database_url();
     pub static ref REDIS_HOST_NAME: String = set_redis_host();
     pub static ref POSTMARK_AUTH_TOKEN: Secret<String> = set_postmark_auth_token();
+    pub static ref PUBLIC_BASE_URL: String = set_public_base_url();
+    // Passkeys are bound to the host users see, so both derive from the public URL
+    pub static ref WEBAUTHN_ORIGIN: String = webauthn_url().origin().ascii_serialization();
+    pub static ref WEBAUTHN_RP_ID: String = webauthn_url()
+        .host_str()
+        .expect("PUBLIC_BASE_URL must have a host.")
+        .to_owned();
+    pub static ref TOTP_ENCRYPTION_KEY: Secret<Vec<u8>> = set_totp_encryption_key();
+    pub static ref UNVERIFIED_LOGIN_POLICY: UnverifiedLoginPolicy = set_unverified_login_policy();
+    pub static ref API_CLIENTS: ApiClients = set_api_clients();
+    pub static ref JWT_ISSUER: String = set_jwt_issuer();
+    pub static ref JWT_AUDIENCE: String = set_jwt_audience();
+    pub static ref JWT_AUDIENCES: Vec<String> = set_jwt_audiences();
+    pub static ref ADMIN_KEY: Option<AdminKey> = set_admin_key();
+    pub static ref TRUSTED_PROXIES: TrustedProxies = set_trusted_proxies();
 }
 
-fn set_token() -> String {
+/// The keyring currently used to sign and verify JWTs.
+pub fn jwt_keyring() -> Arc<JwtKeyring> {
+    JWT_KEYRING
+        .read()
+        .expect("JWT keyring lock poisoned")
+        .clone()
+}
+
+/// Re-read the key files so keys can be added or retired without a restart.
+/// The current keyring stays in place if the new configuration does not load.
+pub fn reload_jwt_keyring() -> Result<()> {
+    let keyring = load_jwt_keyring()?;
+    *JWT_KEYRING
+        .write()
+        .map_err(|_| eyre!("JWT keyring lock poisoned"))? = Arc::new(keyring);
+    Ok(())
+}
+
+fn load_jwt_keyring() -> Result<JwtKeyring> {
     dotenv().ok();
-    let secret = std_env::var(env::JWT_SECRET_ENV_VAR).expect("JWT_SECRET must be set.");
-    if secret.is_empty() {
-        panic!("JWT_SECRET must not be empty.");
+    let signing_key_path = std_env::var(env::JWT_SIGNING_KEY_PATH_ENV_VAR)
+        .wrap_err("JWT_SIGNING_KEY_PATH must be set.")?;
+    if signing_key_path.is_empty() {
+        return Err(eyre!("JWT_SIGNING_KEY_PATH must not be empty."));
     }
-    secret
+
+    let verification_key_paths =
+        verification_key_paths(std_env::var(env::JWT_VERIFICATION_KEYS_DIR_ENV_VAR).ok())?;
+
+    JwtKeyring::from_pem_files(signing_key_path, &verification_key_paths)
+}
+
+/// Every *.pem in the verification directory is a previous key that still verifies tokens.
+/// A directory that doesn't exist holds no previous keys, e.g. before the first rotation.
+fn verification_key_paths(dir: Option<String>) -> Result<Vec<PathBuf>> {
+    let Some(dir) = dir.filter(|dir| !dir.is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e)
+                .wrap_err_with(|| format!("failed to read JWT verification keys dir {}", dir))
+        }
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "pem") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
 }
 
 fn set_postgres_password() -> String {
@@ -73,15 +148,147 @@ fn set_postmark_auth_token() -> Secret<String> {
     )
 }
 
+fn set_totp_encryption_key() -> Secret<Vec<u8>> {
+    dotenv().ok();
+    let encoded =
+        std_env::var(env::TOTP_ENCRYPTION_KEY_ENV_VAR).expect("TOTP_ENCRYPTION_KEY must be set.");
+    let key = STANDARD
+        .decode(encoded.trim())
+        .expect("TOTP_ENCRYPTION_KEY must be base64.");
+    if key.len() != 32 {
+        panic!("TOTP_ENCRYPTION_KEY must decode to 32 bytes.");
+    }
+    Secret::new(key)
+}
+
+fn set_public_base_url() -> String {
+    dotenv().ok();
+    std_env::var(env::PUBLIC_BASE_URL_ENV_VAR)
+        .unwrap_or(DEFAULT_PUBLIC_BASE_URL.to_owned())
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+fn set_unverified_login_policy() -> UnverifiedLoginPolicy {
+    dotenv().ok();
+    std_env::var(env::UNVERIFIED_LOGIN_POLICY_ENV_VAR)
+        .unwrap_or(DEFAULT_UNVERIFIED_LOGIN_POLICY.to_owned())
+        .parse()
+        .expect("UNVERIFIED_LOGIN_POLICY must be \"allow\" or \"deny\".")
+}
+
+fn set_api_clients() -> ApiClients {
+    dotenv().ok();
+    std_env::var(env::API_CLIENTS_ENV_VAR)
+        .unwrap_or_default()
+        .parse()
+        .expect("API_CLIENTS must be a comma-separated list of id:sha256-digest pairs.")
+}
+
+fn set_admin_key() -> Option<AdminKey> {
+    dotenv().ok();
+    std_env::var(env::ADMIN_KEY_DIGEST_ENV_VAR)
+        .ok()
+        .filter(|digest| !digest.trim().is_empty())
+        .map(|digest| {
+            AdminKey::from_hex_digest(digest.trim())
+                .expect("ADMIN_KEY_DIGEST must be a hex-encoded SHA-256 digest.")
+        })
+}
+
+fn set_jwt_issuer() -> String {
+    dotenv().ok();
+    std_env::var(env::JWT_ISSUER_ENV_VAR)
+        .ok()
+        .filter(|issuer| !issuer.is_empty())
+        .unwrap_or(PUBLIC_BASE_URL.clone())
+}
+
+fn set_jwt_audience() -> String {
+    dotenv().ok();
+    std_env::var(env::JWT_AUDIENCE_ENV_VAR)
+        .ok()
+        .filter(|audience| !audience.is_empty())
+        .unwrap_or(DEFAULT_JWT_AUDIENCE.to_owned())
+}
+
+fn set_jwt_audiences() -> Vec<String> {
+    dotenv().ok();
+    std_env::var(env::JWT_AUDIENCES_ENV_VAR)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|audience| !audience.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn set_trusted_proxies() -> TrustedProxies {
+    dotenv().ok();
+    std_env::var(env::TRUSTED_PROXIES_ENV_VAR)
+        .unwrap_or_default()
+        .parse()
+        .expect("TRUSTED_PROXIES must be a comma-separated list of IP addresses or CIDR ranges.")
+}
+
+fn webauthn_url() -> reqwest::Url {
+    reqwest::Url::parse(PUBLIC_BASE_URL.as_str()).expect("PUBLIC_BASE_URL must be a valid URL.")
+}
+
 pub mod env {
-    pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
+    pub const JWT_SIGNING_KEY_PATH_ENV_VAR: &str = "JWT_SIGNING_KEY_PATH";
+    pub const JWT_VERIFICATION_KEYS_DIR_ENV_VAR: &str = "JWT_VERIFICATION_KEYS_DIR";
     pub const POSTGRES_PASSWORD_ENV_VAR: &str = "POSTGRES_PASSWORD";
     pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
     pub const ALLOWED_ORIGINS_ENV_VAR: &str = "ALLOWED_ORIGINS";
     pub const REDIS_HOST_NAME_ENV_VAR: &str = "REDIS_HOST_NAME";
     pub const POSTMARK_AUTH_TOKEN_ENV_VAR: &str = "POSTMARK_AUTH_TOKEN";
+    pub const PUBLIC_BASE_URL_ENV_VAR: &str = "PUBLIC_BASE_URL";
+    pub const TOTP_ENCRYPTION_KEY_ENV_VAR: &str = "TOTP_ENCRYPTION_KEY";
+    pub const UNVERIFIED_LOGIN_POLICY_ENV_VAR: &str = "UNVERIFIED_LOGIN_POLICY";
+    pub const API_CLIENTS_ENV_VAR: &str = "API_CLIENTS";
+    pub const JWT_ISSUER_ENV_VAR: &str = "JWT_ISSUER";
+    pub const JWT_AUDIENCE_ENV_VAR: &str = "JWT_AUDIENCE";
+    pub const JWT_AUDIENCES_ENV_VAR: &str = "JWT_AUDIENCES";
+    pub const ADMIN_KEY_DIGEST_ENV_VAR: &str = "ADMIN_KEY_DIGEST";
+    pub const TRUSTED_PROXIES_ENV_VAR: &str = "TRUSTED_PROXIES";
 }
 
 pub const JWT_COOKIE_NAME: &str = "jwt";
+pub const REFRESH_TOKEN_COOKIE_NAME: &str = "refresh_token";
+pub const MAGIC_LINK_NONCE_COOKIE_NAME: &str = "magic_link_nonce";
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
 pub const DEFAULT_ALLOWED_ORIGINS: &str = "https://idlelgr.duckdns.org,http://localhost:8000";
 pub const DEFAULT_REDIS_HOSTNAME: &str = "127.0.0.1";
+// Where users reach the auth-service UI; links in emails are built from it
+pub const DEFAULT_PUBLIC_BASE_URL: &str = "https://idlelgr.duckdns.org/auth";
+pub const DEFAULT_UNVERIFIED_LOGIN_POLICY: &str = "deny";
+pub const DEFAULT_JWT_AUDIENCE: &str = "auth-service";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_missing_verification_keys_dir_holds_no_keys() {
+        let missing = std_env::temp_dir().join(Uuid::new_v4().to_string());
+        let paths = verification_key_paths(Some(missing.display().to_string())).unwrap();
+        assert!(paths.is_empty());
+        assert!(verification_key_paths(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_verification_keys_dir_lists_pem_files() {
+        let dir = std_env::temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir(&dir).unwrap();
+        for name in ["b.pem", "a.pem", "notes.txt"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let paths = verification_key_paths(Some(dir.display().to_string())).unwrap();
+        assert_eq!(paths, vec![dir.join("a.pem"), dir.join("b.pem")]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
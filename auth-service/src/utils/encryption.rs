@@ -0,0 +1,83 @@
+use color_eyre::eyre::{eyre, Result};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use secrecy::{ExposeSecret, Secret};
+
+/// Encrypt `plaintext` with AES-256-GCM under `key`.
+/// The random nonce is prepended to the ciphertext so the result is self-contained.
+pub fn encrypt(key: &Secret<Vec<u8>>, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let key = aead_key(key)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| eyre!("failed to generate nonce"))?;
+
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut in_out,
+    )
+    .map_err(|_| eyre!("failed to encrypt"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&in_out);
+    Ok(sealed)
+}
+
+/// Reverse of [`encrypt`]; fails if the data was tampered with or the key is wrong.
+pub fn decrypt(key: &Secret<Vec<u8>>, sealed: &[u8]) -> Result<Secret<Vec<u8>>> {
+    let key = aead_key(key)?;
+    if sealed.len() < NONCE_LEN {
+        return Err(eyre!("ciphertext is too short"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| eyre!("invalid nonce"))?;
+
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::empty(), &mut in_out)
+        .map_err(|_| eyre!("failed to decrypt"))?;
+    Ok(Secret::new(plaintext.to_vec()))
+}
+
+fn aead_key(key: &Secret<Vec<u8>>) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key.expose_secret())
+        .map_err(|_| eyre!("encryption key must be 32 bytes"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> Secret<Vec<u8>> {
+        Secret::new(vec![byte; 32])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sealed = encrypt(&key(1), b"totp secret").unwrap();
+        assert_ne!(&sealed[NONCE_LEN..], b"totp secret");
+        let opened = decrypt(&key(1), &sealed).unwrap();
+        assert_eq!(opened.expose_secret(), b"totp secret");
+    }
+
+    #[test]
+    fn test_decrypt_fails_with_wrong_key_or_tampering() {
+        let mut sealed = encrypt(&key(1), b"totp secret").unwrap();
+        assert!(decrypt(&key(2), &sealed).is_err());
+
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(decrypt(&key(1), &sealed).is_err());
+        assert!(decrypt(&key(1), &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_short_key() {
+        assert!(encrypt(&Secret::new(vec![0; 16]), b"x").is_err());
+    }
+}
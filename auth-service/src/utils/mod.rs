@@ -1,7 +1,17 @@
 mod auth;
 mod constants;
+mod encryption;
+mod jwt_keys;
+mod password_hash;
+mod rate_limit;
 mod tracing;
+mod webauthn;
 
 pub use auth::*;
 pub use constants::*;
+pub use encryption::*;
+pub use jwt_keys::*;
+pub use password_hash::*;
+pub use rate_limit::*;
 pub use tracing::*;
+pub use webauthn::*;
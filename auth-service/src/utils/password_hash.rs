@@ -0,0 +1,50 @@
+use argon2::{
+    password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash, PasswordHasher,
+    PasswordVerifier, Version,
+};
+use color_eyre::eyre::{eyre, Context, Result};
+use secrecy::{ExposeSecret, Secret};
+
+#[tracing::instrument(name = "Verify password hash", skip_all)]
+pub async fn verify_password_hash(
+    expected_password_hash: &Secret<String>,
+    password_candidate: &Secret<String>,
+) -> Result<()> {
+    let current_span: tracing::Span = tracing::Span::current();
+
+    // Clone the inner strings here (safe within the current scope) and move into blocking code.
+    let expected_clone = expected_password_hash.expose_secret().clone();
+    let candidate_clone = password_candidate.expose_secret().clone();
+
+    tokio::task::spawn_blocking(move || {
+        current_span.in_scope(|| {
+            let parsed_hash = PasswordHash::new(expected_clone.as_str())?;
+            Argon2::default()
+                .verify_password(candidate_clone.as_bytes(), &parsed_hash)
+                .wrap_err("failed to verify password hash")
+        })
+    })
+    .await?
+}
+
+#[tracing::instrument(name = "Computing password hash", skip_all)]
+pub async fn compute_password_hash(password: Secret<String>) -> Result<String> {
+    let current_span: tracing::Span = tracing::Span::current();
+
+    let password_hash = tokio::task::spawn_blocking(move || -> Result<String> {
+        current_span.in_scope(|| {
+            let find_hash = password.expose_secret();
+            let salt = SaltString::generate(&mut rand::thread_rng());
+            let params = Params::new(15000, 2, 1, None)
+                .map_err(|e| eyre!("invalid argon2 params: {}", e))?;
+            let argon = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+            let ph = argon
+                .hash_password(find_hash.as_bytes(), &salt)
+                .map_err(|e| eyre!("failed to hash password: {}", e))?;
+            Ok(ph.to_string())
+        })
+    })
+    .await??;
+
+    Ok(password_hash)
+}
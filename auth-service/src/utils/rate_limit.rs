@@ -0,0 +1,111 @@
+use crate::domain::{
+    AuthAPIError, ClientInfo, Email, RateLimit, RateLimitStore, RouteRateLimit, TrustedProxies,
+};
+use axum::{
+    body::{to_bytes, Body},
+    extract::{FromRef, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use color_eyre::eyre::eyre;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+// Bodies are read up to this size to find the email; login requests are far smaller
+const MAX_RATE_LIMITED_BODY_BYTES: usize = 16 * 1024;
+const UNKNOWN_IP: &str = "unknown";
+
+/// State of the `rate_limit` middleware on one group of routes.
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<RwLock<dyn RateLimitStore + Send + Sync>>,
+    limit: RouteRateLimit,
+    trusted_proxies: TrustedProxies,
+}
+
+impl RateLimiter {
+    pub fn new(
+        store: Arc<RwLock<dyn RateLimitStore + Send + Sync>>,
+        limit: RouteRateLimit,
+        trusted_proxies: TrustedProxies,
+    ) -> Self {
+        Self {
+            store,
+            limit,
+            trusted_proxies,
+        }
+    }
+
+    async fn check(&self, key: String, limit: &RateLimit) -> Result<(), AuthAPIError> {
+        let retry_after = self
+            .store
+            .write()
+            .await
+            .record_attempt(&key, limit)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(eyre!(e)))?;
+        match retry_after {
+            Some(retry_after) => Err(AuthAPIError::TooManyRequests { retry_after }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromRef<RateLimiter> for TrustedProxies {
+    fn from_ref(limiter: &RateLimiter) -> Self {
+        limiter.trusted_proxies.clone()
+    }
+}
+
+// Clients whose address is unknown share one allowance rather than going unlimited
+fn ip_key(name: &str, ip: Option<&str>) -> String {
+    format!("{}:ip:{}", name, ip.unwrap_or(UNKNOWN_IP))
+}
+
+#[derive(Deserialize)]
+struct EmailField {
+    email: String,
+}
+
+/// Count the request against the client's address and against the `email` of its JSON body,
+/// answering 429 once either is over the limit. Requests turned away don't count.
+#[tracing::instrument(name = "Rate Limit", skip_all)]
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    client: ClientInfo,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError> {
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, MAX_RATE_LIMITED_BODY_BYTES)
+        .await
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let name = limiter.limit.name;
+
+    limiter
+        .check(ip_key(name, client.ip.as_deref()), &limiter.limit.per_ip)
+        .await?;
+    // Parsed so that every spelling of an address shares its allowance
+    let email = serde_json::from_slice::<EmailField>(&body)
+        .ok()
+        .and_then(|field| Email::parse(Secret::new(field.email)).ok());
+    if let Some(email) = email {
+        let key = format!("{}:email:{}", name, email.as_ref().expose_secret());
+        limiter.check(key, &limiter.limit.per_email).await?;
+    }
+
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_key() {
+        assert_eq!(ip_key("login", Some("10.0.0.1")), "login:ip:10.0.0.1");
+        assert_eq!(ip_key("login", None), "login:ip:unknown");
+    }
+}
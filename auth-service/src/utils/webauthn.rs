@@ -0,0 +1,266 @@
+use crate::domain::{OneTimeToken, PasskeyPublicKey, COSE_ALG_EDDSA, COSE_ALG_ES256};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::Value;
+use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use ring::digest;
+use secrecy::Secret;
+use serde::Deserialize;
+
+// authenticatorData flags (WebAuthn §6.1)
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+// COSE key parameters (RFC 9052 / 9053)
+const COSE_KEY_KTY: i64 = 1;
+const COSE_KEY_ALG: i64 = 3;
+const COSE_KEY_CRV: i64 = -1;
+const COSE_KEY_X: i64 = -2;
+const COSE_KEY_Y: i64 = -3;
+const COSE_KTY_OKP: i64 = 1;
+const COSE_KTY_EC2: i64 = 2;
+const COSE_CRV_P256: i64 = 1;
+const COSE_CRV_ED25519: i64 = 6;
+
+/// Which ceremony a clientDataJSON belongs to.
+#[derive(Debug, Clone, Copy)]
+pub enum CeremonyType {
+    Create,
+    Get,
+}
+
+impl CeremonyType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CeremonyType::Create => "webauthn.create",
+            CeremonyType::Get => "webauthn.get",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ClientDataJson {
+    #[serde(rename = "type")]
+    ceremony_type: String,
+    challenge: String,
+    origin: String,
+}
+
+/// The parts of clientDataJSON the server acts on, once type and origin have been checked.
+pub struct ClientData {
+    /// The challenge we issued; it is a one-time token stored server-side
+    pub challenge: OneTimeToken,
+    /// SHA-256 of the raw clientDataJSON, which the authenticator signs over
+    pub hash: Vec<u8>,
+}
+
+pub fn verify_client_data(
+    client_data_json: &[u8],
+    ceremony: CeremonyType,
+    expected_origin: &str,
+) -> Result<ClientData> {
+    let client_data: ClientDataJson =
+        serde_json::from_slice(client_data_json).wrap_err("malformed clientDataJSON")?;
+    if client_data.ceremony_type != ceremony.as_str() {
+        return Err(eyre!(
+            "unexpected ceremony type {}",
+            client_data.ceremony_type
+        ));
+    }
+    if client_data.origin != expected_origin {
+        return Err(eyre!("unexpected origin {}", client_data.origin));
+    }
+
+    let challenge = String::from_utf8(decode_base64url(&client_data.challenge)?)
+        .wrap_err("challenge is not a token we issued")?;
+    let challenge = OneTimeToken::parse(Secret::new(challenge))?;
+
+    Ok(ClientData {
+        challenge,
+        hash: digest::digest(&digest::SHA256, client_data_json)
+            .as_ref()
+            .to_vec(),
+    })
+}
+
+/// Credential created during registration.
+pub struct AttestedCredential {
+    pub id: Vec<u8>,
+    pub public_key: PasskeyPublicKey,
+}
+
+pub struct AuthenticatorData {
+    /// The authenticator checked a PIN or biometric, not just a touch
+    pub user_verified: bool,
+    pub sign_count: u32,
+    pub attested_credential: Option<AttestedCredential>,
+}
+
+/// Parse authenticatorData, checking it was produced for our RP ID with the user present.
+pub fn parse_authenticator_data(bytes: &[u8], rp_id: &str) -> Result<AuthenticatorData> {
+    if bytes.len() < 37 {
+        return Err(eyre!("authenticatorData is too short"));
+    }
+    let (rp_id_hash, rest) = bytes.split_at(32);
+    if rp_id_hash != digest::digest(&digest::SHA256, rp_id.as_bytes()).as_ref() {
+        return Err(eyre!("authenticatorData is for another RP ID"));
+    }
+    let flags = rest[0];
+    if flags & FLAG_USER_PRESENT == 0 {
+        return Err(eyre!("user was not present"));
+    }
+    let sign_count = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]);
+
+    let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+        Some(parse_attested_credential(&rest[5..])?)
+    } else {
+        None
+    };
+
+    Ok(AuthenticatorData {
+        user_verified: flags & FLAG_USER_VERIFIED != 0,
+        sign_count,
+        attested_credential,
+    })
+}
+
+/// Parse an attestationObject and return its authenticatorData.
+/// Passkeys are requested with `attestation: "none"`, so the attestation statement is not checked.
+pub fn parse_attestation_object(bytes: &[u8], rp_id: &str) -> Result<AuthenticatorData> {
+    let value: Value = ciborium::from_reader(bytes).wrap_err("malformed attestationObject")?;
+    let auth_data = map_entry(&value, &Value::Text("authData".to_owned()))
+        .and_then(Value::as_bytes)
+        .context("attestationObject has no authData")?;
+    parse_authenticator_data(auth_data, rp_id)
+}
+
+/// Decode base64url as sent by browsers, with or without padding.
+pub fn decode_base64url(s: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(s.trim_end_matches('='))
+        .wrap_err("invalid base64url")
+}
+
+fn parse_attested_credential(bytes: &[u8]) -> Result<AttestedCredential> {
+    // aaguid (16 bytes), credential id length (2 bytes), credential id, COSE public key
+    if bytes.len() < 18 {
+        return Err(eyre!("attested credential data is too short"));
+    }
+    let id_len = u16::from_be_bytes([bytes[16], bytes[17]]) as usize;
+    let id = bytes
+        .get(18..18 + id_len)
+        .context("credential id is truncated")?
+        .to_vec();
+
+    // The key is the first CBOR item after the id; extensions may follow it
+    let mut rest = &bytes[18 + id_len..];
+    let cose_key: Value = ciborium::from_reader(&mut rest).wrap_err("malformed COSE key")?;
+    let public_key = parse_cose_key(&cose_key)?;
+
+    Ok(AttestedCredential { id, public_key })
+}
+
+fn parse_cose_key(key: &Value) -> Result<PasskeyPublicKey> {
+    let int = |label: i64| {
+        map_entry(key, &Value::Integer(label.into()))
+            .and_then(Value::as_integer)
+            .and_then(|i| i64::try_from(i).ok())
+    };
+    let bytes = |label: i64| {
+        map_entry(key, &Value::Integer(label.into()))
+            .and_then(Value::as_bytes)
+            .cloned()
+    };
+
+    match (int(COSE_KEY_KTY), int(COSE_KEY_ALG), int(COSE_KEY_CRV)) {
+        (Some(COSE_KTY_EC2), Some(COSE_ALG_ES256), Some(COSE_CRV_P256)) => {
+            let x = bytes(COSE_KEY_X).context("EC2 key has no x")?;
+            let y = bytes(COSE_KEY_Y).context("EC2 key has no y")?;
+            let mut point = vec![0x04];
+            point.extend_from_slice(&x);
+            point.extend_from_slice(&y);
+            PasskeyPublicKey::from_parts(COSE_ALG_ES256, point)
+        }
+        (Some(COSE_KTY_OKP), Some(COSE_ALG_EDDSA), Some(COSE_CRV_ED25519)) => {
+            let x = bytes(COSE_KEY_X).context("OKP key has no x")?;
+            PasskeyPublicKey::from_parts(COSE_ALG_EDDSA, x)
+        }
+        _ => Err(eyre!("unsupported COSE key")),
+    }
+}
+
+fn map_entry<'a>(map: &'a Value, key: &Value) -> Option<&'a Value> {
+    map.as_map()?.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth_data(rp_id: &str, flags: u8, sign_count: u32) -> Vec<u8> {
+        let mut data = digest::digest(&digest::SHA256, rp_id.as_bytes())
+            .as_ref()
+            .to_vec();
+        data.push(flags);
+        data.extend_from_slice(&sign_count.to_be_bytes());
+        data
+    }
+
+    fn client_data(ceremony: &str, challenge: &str, origin: &str) -> Vec<u8> {
+        serde_json::json!({
+            "type": ceremony,
+            "challenge": URL_SAFE_NO_PAD.encode(challenge),
+            "origin": origin,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_parse_authenticator_data() {
+        let data =
+            parse_authenticator_data(&auth_data("example.com", 0x05, 7), "example.com").unwrap();
+        assert_eq!(data.sign_count, 7);
+        assert!(data.user_verified);
+        assert!(data.attested_credential.is_none());
+    }
+
+    #[test]
+    fn test_parse_authenticator_data_checks_rp_and_presence() {
+        assert!(parse_authenticator_data(&auth_data("evil.com", 0x01, 0), "example.com").is_err());
+        assert!(
+            parse_authenticator_data(&auth_data("example.com", 0x04, 0), "example.com").is_err()
+        );
+        assert!(parse_authenticator_data(&[0; 10], "example.com").is_err());
+    }
+
+    #[test]
+    fn test_verify_client_data() {
+        let token = "a".repeat(43);
+        let json = client_data("webauthn.get", &token, "https://example.com");
+        let parsed = verify_client_data(&json, CeremonyType::Get, "https://example.com").unwrap();
+        assert_eq!(
+            parsed.challenge,
+            OneTimeToken::parse(Secret::new(token)).unwrap()
+        );
+        assert_eq!(parsed.hash.len(), 32);
+    }
+
+    #[test]
+    fn test_verify_client_data_rejects_mismatches() {
+        let token = "a".repeat(43);
+        let origin = "https://example.com";
+        let wrong_type = client_data("webauthn.create", &token, origin);
+        assert!(verify_client_data(&wrong_type, CeremonyType::Get, origin).is_err());
+        let wrong_origin = client_data("webauthn.get", &token, "https://evil.com");
+        assert!(verify_client_data(&wrong_origin, CeremonyType::Get, origin).is_err());
+        let wrong_challenge = client_data("webauthn.get", "short", origin);
+        assert!(verify_client_data(&wrong_challenge, CeremonyType::Get, origin).is_err());
+    }
+
+    #[test]
+    fn test_decode_base64url_accepts_padding() {
+        assert_eq!(decode_base64url("YQ==").unwrap(), b"a");
+        assert_eq!(decode_base64url("YQ").unwrap(), b"a");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{
+        ErrorResponse, TwoFACodeStore, UserStore, UserStoreError, ACCOUNT_DELETION_GRACE_SECONDS,
+    },
+    routes::{purge_deleted_accounts, DeleteAccountResponse},
+};
+use chrono::Utc;
+
+async fn signup_and_login(app: &TestApp, requires_2fa: bool) -> String {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": requires_2fa
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(
+        response.status().as_u16(),
+        if requires_2fa { 206 } else { 200 }
+    );
+    email
+}
+
+async fn login_status(app: &TestApp, email: &str) -> (u16, String) {
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    let status = response.status().as_u16();
+    let error = response
+        .json::<ErrorResponse>()
+        .await
+        .map(|body| body.error)
+        .unwrap_or_default();
+    (status, error)
+}
+
+#[tokio::test]
+async fn should_schedule_deletion_and_end_sessions() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app, false).await;
+
+    let response = app
+        .post_account_delete(&serde_json::json!({ "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+    let body = response
+        .json::<DeleteAccountResponse>()
+        .await
+        .expect("Could not deserialize response body to DeleteAccountResponse");
+    let expected = Utc::now().timestamp() + ACCOUNT_DELETION_GRACE_SECONDS as i64;
+    assert!((body.delete_after - expected).abs() < 60);
+
+    assert!(
+        app.get_emailed_token(&email).is_some(),
+        "A cancellation link should have been emailed"
+    );
+
+    // Every session is over and no new one can be started
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        login_status(&app, &email).await,
+        (403, "Account pending deletion".to_owned())
+    );
+
+    // The account itself stays until the grace period is over
+    let user_id = app.get_user_id(&email).await;
+    let user = app.user_store.read().await.get_user(&user_id).await;
+    assert!(user.expect("User should still exist").pending_deletion);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_require_password() {
+    let mut app = TestApp::new().await;
+
+    let body = serde_json::json!({ "password": "Password123!" });
+    let response = app.post_account_delete(&body).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let email = signup_and_login(&app, false).await;
+    let response = app
+        .post_account_delete(&serde_json::json!({ "password": "WrongPassword123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await.0, 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_cancel_deletion_with_emailed_link() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app, false).await;
+
+    let response = app
+        .post_account_delete(&serde_json::json!({ "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+    let token = app
+        .get_emailed_token(&email)
+        .expect("A cancellation link should have been emailed");
+
+    let response = app.get_account_delete_cancel(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(login_status(&app, &email).await.0, 200);
+
+    // The link works once
+    let response = app.get_account_delete_cancel(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_purge_accounts_after_grace_period() {
+    let mut app = TestApp::new().await;
+    // A login waiting for its 2FA code leaves the code in Redis
+    let due_email = signup_and_login(&app, true).await;
+    let kept_email = signup_and_login(&app, false).await;
+    let due_id = app.get_user_id(&due_email).await;
+    let kept_id = app.get_user_id(&kept_email).await;
+
+    {
+        let mut store = app.user_store.write().await;
+        store.schedule_deletion(&due_id, 0).await.unwrap();
+        store
+            .schedule_deletion(&kept_id, ACCOUNT_DELETION_GRACE_SECONDS)
+            .await
+            .unwrap();
+    }
+
+    let purged = purge_deleted_accounts(&app.app_state)
+        .await
+        .expect("Failed to purge deleted accounts");
+    assert_eq!(purged, 1);
+
+    let store = app.user_store.read().await;
+    assert_eq!(
+        store.get_user(&due_id).await.map(|_| ()),
+        Err(UserStoreError::UserNotFound)
+    );
+    assert!(store.get_user(&kept_id).await.is_ok());
+    drop(store);
+    assert!(app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&due_id)
+        .await
+        .is_err());
+
+    let purged = purge_deleted_accounts(&app.app_state)
+        .await
+        .expect("Failed to purge deleted accounts");
+    assert_eq!(purged, 0);
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,250 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{LoginAttemptId, Role, TwoFACode, TwoFACodeStore, UserStore, ADMIN_ROLE},
+    routes::{ConfirmEmailChangeResponse, SessionsResponse},
+};
+
+async fn signup_and_login(app: &TestApp) -> String {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    email
+}
+
+async fn login_status(app: &TestApp, email: &str) -> u16 {
+    app.post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_move_account_once_new_address_confirms() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+    let new_email = get_random_email();
+    let user_id = app.get_user_id(&email).await;
+    app.user_store
+        .write()
+        .await
+        .assign_role(&user_id, &Role::parse(ADMIN_ROLE).unwrap())
+        .await
+        .expect("Failed to assign role");
+    // As if a login were waiting for its 2FA code
+    let code = TwoFACode::default();
+    app.two_fa_code_store
+        .write()
+        .await
+        .add_code(user_id, LoginAttemptId::default(), code.clone())
+        .await
+        .unwrap();
+
+    let body = serde_json::json!({ "newEmail": new_email, "password": "Password123!" });
+    let response = app.post_account_email(&body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    assert!(
+        app.get_emailed_token(&email).is_some(),
+        "The old address should have been told"
+    );
+    let token = app
+        .get_emailed_token(&new_email)
+        .expect("A confirmation link should have been emailed");
+
+    // Nothing changes before the new address confirms
+    assert_eq!(login_status(&app, &new_email).await, 401);
+
+    let response = app.get_account_email_confirm(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response
+        .json::<ConfirmEmailChangeResponse>()
+        .await
+        .expect("Could not deserialize response body to ConfirmEmailChangeResponse");
+    assert_eq!(body.email, new_email);
+
+    // Sessions belong to the user, not the address, so they carry on untouched
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse");
+    assert_eq!(sessions.sessions.len(), 1);
+
+    // The user keeps their id, and with it everything keyed by it
+    assert_eq!(app.get_user_id(&new_email).await, user_id);
+    let store = app.user_store.read().await;
+    let roles = store.get_roles(&user_id).await.unwrap();
+    assert_eq!(roles.roles, vec![ADMIN_ROLE.to_owned()]);
+    assert!(store.get_user(&user_id).await.unwrap().email_verified);
+    drop(store);
+    let (_, kept_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&user_id)
+        .await
+        .unwrap();
+    assert_eq!(kept_code, code);
+
+    assert_eq!(login_status(&app, &email).await, 401);
+    assert_eq!(login_status(&app, &new_email).await, 200);
+
+    // The link works once
+    let response = app.get_account_email_confirm(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_let_old_address_cancel() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+    let new_email = get_random_email();
+
+    let body = serde_json::json!({ "newEmail": new_email, "password": "Password123!" });
+    let response = app.post_account_email(&body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let confirm_token = app.get_emailed_token(&new_email).unwrap();
+    let cancel_token = app
+        .get_emailed_token(&email)
+        .expect("A cancel link should have been emailed");
+
+    let response = app.get_account_email_cancel(&cancel_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Whoever asked for the change is logged out
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.get_account_email_confirm(&confirm_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await, 200);
+    assert_eq!(login_status(&app, &new_email).await, 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_let_old_address_undo_confirmed_change() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+    let new_email = get_random_email();
+
+    let body = serde_json::json!({ "newEmail": new_email, "password": "Password123!" });
+    let response = app.post_account_email(&body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let cancel_token = app.get_emailed_token(&email).unwrap();
+    let confirm_token = app.get_emailed_token(&new_email).unwrap();
+    let response = app.get_account_email_confirm(&confirm_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let revert_token = app
+        .get_emailed_token(&email)
+        .expect("The old address should have been told of the change");
+    assert_ne!(revert_token, cancel_token);
+
+    // While the change can be undone, the old address stays the account's
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": "Password123!",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 409);
+
+    // The link sent with the request works after confirmation too
+    let response = app.get_account_email_cancel(&cancel_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Whoever made the change is logged out
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await, 200);
+    assert_eq!(login_status(&app, &new_email).await, 401);
+
+    // Undone once; the later link has nothing left to undo
+    let response = app.get_account_email_cancel(&revert_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(login_status(&app, &email).await, 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_only_confirm_latest_request() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+    let first_email = get_random_email();
+    let second_email = get_random_email();
+
+    let body = serde_json::json!({ "newEmail": first_email, "password": "Password123!" });
+    let response = app.post_account_email(&body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let first_token = app.get_emailed_token(&first_email).unwrap();
+
+    let body = serde_json::json!({ "newEmail": second_email, "password": "Password123!" });
+    let response = app.post_account_email(&body).await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    let response = app.get_account_email_confirm(&first_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &second_email).await, 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_reject_invalid_requests() {
+    let mut app = TestApp::new().await;
+
+    let body = serde_json::json!({ "newEmail": get_random_email(), "password": "Password123!" });
+    let response = app.post_account_email(&body).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let taken_email = signup_and_login(&app).await;
+    let email = signup_and_login(&app).await;
+
+    let test_cases = [
+        (
+            serde_json::json!({ "newEmail": "not-an-email", "password": "Password123!" }),
+            400,
+        ),
+        (
+            serde_json::json!({ "newEmail": email, "password": "Password123!" }),
+            400,
+        ),
+        (
+            serde_json::json!({ "newEmail": get_random_email(), "password": "WrongPassword123!" }),
+            401,
+        ),
+        (
+            serde_json::json!({ "newEmail": taken_email, "password": "Password123!" }),
+            409,
+        ),
+    ];
+    for (body, status) in test_cases {
+        let response = app.post_account_email(&body).await;
+        assert_eq!(
+            response.status().as_u16(),
+            status,
+            "Failed for input: {:?}",
+            body
+        );
+    }
+
+    let response = app.get_account_email_confirm("not-a-token").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
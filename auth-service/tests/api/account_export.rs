@@ -0,0 +1,84 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::routes::AccountExportResponse;
+
+#[tokio::test]
+async fn should_export_personal_data() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app
+        .post_account_consent(&serde_json::json!({ "purpose": "newsletter", "granted": true }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_account_export().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let disposition = response
+        .headers()
+        .get("content-disposition")
+        .expect("The export should be served as a download");
+    assert!(disposition.to_str().unwrap().starts_with("attachment"));
+
+    let text = response.text().await.unwrap();
+    // Neither the password hash nor any token may leave the service
+    assert!(!text.contains("$argon2"));
+    assert!(!text.to_lowercase().contains("password123"));
+
+    let export: AccountExportResponse =
+        serde_json::from_str(&text).expect("Could not deserialize AccountExportResponse");
+    assert_eq!(export.email, email);
+
+    let account = &export.data["account"];
+    assert_eq!(account["email"], email.as_str());
+    assert_eq!(account["twoFactor"]["required"], false);
+    assert_eq!(account["twoFactor"]["totpEnabled"], false);
+    assert!(account.get("passwordHash").is_none());
+
+    let sessions = export.data["sessions"]
+        .as_array()
+        .expect("Sessions should be a list");
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0]["createdAt"].as_i64().is_some());
+
+    let events: Vec<&str> = export.data["auditEvents"]
+        .as_array()
+        .expect("Audit events should be a list")
+        .iter()
+        .map(|event| event["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(events, ["signup", "login", "consent_changed"]);
+    assert_eq!(export.data["auditEvents"][1]["ip"], "127.0.0.1");
+
+    let consents = export.data["consents"]
+        .as_array()
+        .expect("Consents should be a list");
+    assert_eq!(consents.len(), 1);
+    assert_eq!(consents[0]["purpose"], "newsletter");
+    assert_eq!(consents[0]["granted"], true);
+
+    assert_eq!(export.data["passkeys"], serde_json::json!([]));
+    // Stores holding nothing but secrets leave no section behind
+    assert!(export.data.get("oneTimeTokens").is_none());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_require_authentication() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_account_export().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
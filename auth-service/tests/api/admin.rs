@@ -0,0 +1,332 @@
+use crate::helpers::{get_random_email, TestApp, ADMIN_KEY};
+use auth_service::{
+    domain::{
+        ErrorResponse, RecoveryCode, Role, UserId, UserStore, ADMIN_ROLE, RECOVERY_CODE_COUNT,
+    },
+    routes::{AdminUserResponse, AdminUsersResponse, TokenResponse},
+};
+use secrecy::ExposeSecret;
+use uuid::Uuid;
+
+async fn signup(app: &TestApp, email: &str) {
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+async fn login(app: &TestApp, email: &str, password: &str) -> reqwest::Response {
+    app.post_login_token(&serde_json::json!({ "email": email, "password": password }))
+        .await
+}
+
+async fn error_message(response: reqwest::Response) -> String {
+    response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse")
+        .error
+}
+
+async fn admin_user(response: reqwest::Response) -> AdminUserResponse {
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<AdminUserResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserResponse")
+}
+
+#[tokio::test]
+async fn should_require_admin_role_or_key() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_admin_users(&[], None).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.get_admin_users(&[], Some("wrong-key")).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let email = get_random_email();
+    signup(&app, &email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.get_admin_users(&[], None).await;
+    assert_eq!(response.status().as_u16(), 403);
+
+    // Once the role is in the user's token, the cookie alone gets in
+    let user_id = app.get_user_id(&email).await;
+    app.user_store
+        .write()
+        .await
+        .assign_role(&user_id, &Role::parse(ADMIN_ROLE).unwrap())
+        .await
+        .expect("Failed to assign role");
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.get_admin_users(&[], None).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let user = admin_user(app.get_admin_user(&user_id, None).await).await;
+    assert_eq!(user.id, user_id.to_string());
+    assert_eq!(user.roles, Some(vec![ADMIN_ROLE.to_owned()]));
+
+    // A wrong key is refused even alongside an admin's cookie
+    let response = app.get_admin_users(&[], Some("wrong-key")).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_list_and_search_users_with_pagination() {
+    let mut app = TestApp::new().await;
+    let tag = Uuid::new_v4().simple().to_string();
+    for i in 0..3 {
+        signup(&app, &format!("{}-{}@example.com", tag, i)).await;
+    }
+    signup(&app, &get_random_email()).await;
+
+    let response = app
+        .get_admin_users(
+            &[("search", &tag.to_uppercase()), ("perPage", "2")],
+            Some(ADMIN_KEY),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let page = response
+        .json::<AdminUsersResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUsersResponse");
+    assert_eq!(page.total, 3);
+    assert_eq!(page.page, 1);
+    assert_eq!(page.per_page, 2);
+    let emails: Vec<_> = page.users.iter().map(|u| u.email.as_str()).collect();
+    assert_eq!(
+        emails,
+        vec![
+            format!("{}-0@example.com", tag),
+            format!("{}-1@example.com", tag)
+        ]
+    );
+    assert!(page.users.iter().all(|u| u.roles.is_none()));
+
+    let response = app
+        .get_admin_users(
+            &[("search", &tag), ("perPage", "2"), ("page", "2")],
+            Some(ADMIN_KEY),
+        )
+        .await;
+    let page = response
+        .json::<AdminUsersResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUsersResponse");
+    assert_eq!(page.users.len(), 1);
+    assert_eq!(page.users[0].email, format!("{}-2@example.com", tag));
+
+    let response = app.get_admin_users(&[], Some(ADMIN_KEY)).await;
+    let page = response
+        .json::<AdminUsersResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUsersResponse");
+    assert_eq!(page.total, 4);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_404_for_unknown_user() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .get_admin_user(&UserId::default(), Some(ADMIN_KEY))
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+    assert_eq!(error_message(response).await, "User not found");
+
+    let response = app
+        .post_admin_user_action(&UserId::default(), "disable", Some(ADMIN_KEY))
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_disable_and_enable_user() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+    let user_id = app.get_user_id(&email).await;
+    let response = login(&app, &email, "Password123!").await;
+    let tokens = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+
+    let user = admin_user(
+        app.post_admin_user_action(&user_id, "disable", Some(ADMIN_KEY))
+            .await,
+    )
+    .await;
+    assert!(user.disabled);
+
+    // The existing session is gone and no new one can be started
+    let response = app.get_sessions_with_bearer(&tokens.access_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = login(&app, &email, "Password123!").await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(error_message(response).await, "Account disabled");
+
+    let user = admin_user(
+        app.post_admin_user_action(&user_id, "enable", Some(ADMIN_KEY))
+            .await,
+    )
+    .await;
+    assert!(!user.disabled);
+    let response = login(&app, &email, "Password123!").await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_force_password_reset() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+    let user_id = app.get_user_id(&email).await;
+
+    let user = admin_user(
+        app.post_admin_user_action(&user_id, "force-password-reset", Some(ADMIN_KEY))
+            .await,
+    )
+    .await;
+    assert!(user.password_reset_required);
+
+    let response = login(&app, &email, "Password123!").await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(error_message(response).await, "Password reset required");
+
+    let token = app
+        .get_emailed_token(&email)
+        .expect("A password reset link should have been emailed");
+    let body = serde_json::json!({ "token": token, "newPassword": "NewPassword123!" });
+    let response = app.post_password_reset_confirm(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = login(&app, &email, "NewPassword123!").await;
+    assert_eq!(response.status().as_u16(), 200);
+    let user = admin_user(app.get_admin_user(&user_id, Some(ADMIN_KEY)).await).await;
+    assert!(!user.password_reset_required);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_toggle_requires_2fa() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+    let user_id = app.get_user_id(&email).await;
+
+    let body = serde_json::json!({ "requires2FA": true });
+    let user = admin_user(
+        app.post_admin_requires_2fa(&user_id, &body, Some(ADMIN_KEY))
+            .await,
+    )
+    .await;
+    assert!(user.requires_2fa);
+    let response = login(&app, &email, "Password123!").await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let body = serde_json::json!({ "requires2FA": false });
+    let user = admin_user(
+        app.post_admin_requires_2fa(&user_id, &body, Some(ADMIN_KEY))
+            .await,
+    )
+    .await;
+    assert!(!user.requires_2fa);
+    let response = login(&app, &email, "Password123!").await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_email_recovery_codes_when_enabling_2fa() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+    let user_id = app.get_user_id(&email).await;
+
+    let body = serde_json::json!({ "requires2FA": true });
+    let response = app
+        .post_admin_requires_2fa(&user_id, &body, Some(ADMIN_KEY))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let sent = app.email_client.sent_emails();
+    let sent = sent
+        .iter()
+        .rev()
+        .find(|sent| sent.recipient.as_ref().expose_secret() == &email)
+        .expect("No email sent to the user");
+    assert_eq!(sent.subject, "Your recovery codes");
+    let remaining = app
+        .user_store
+        .read()
+        .await
+        .count_recovery_codes(&user_id)
+        .await
+        .unwrap();
+    assert_eq!(remaining, RECOVERY_CODE_COUNT);
+    let codes: Vec<&str> = sent
+        .content
+        .lines()
+        .filter(|line| RecoveryCode::parse(line).is_ok())
+        .collect();
+    assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+
+    // Already on: the codes the user holds stay valid
+    let emails_sent = app.email_client.sent_emails().len();
+    let response = app
+        .post_admin_requires_2fa(&user_id, &body, Some(ADMIN_KEY))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(app.email_client.sent_emails().len(), emails_sent);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_delete_user() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+    let user_id = app.get_user_id(&email).await;
+    let response = login(&app, &email, "Password123!").await;
+    let tokens = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+
+    let response = app.delete_admin_user(&user_id, Some(ADMIN_KEY)).await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = app.get_sessions_with_bearer(&tokens.access_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app.get_admin_user(&user_id, Some(ADMIN_KEY)).await;
+    assert_eq!(response.status().as_u16(), 404);
+    let response = login(&app, &email, "Password123!").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.delete_admin_user(&user_id, Some(ADMIN_KEY)).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,114 @@
+use crate::helpers::{get_random_email, TestApp, API_CLIENT_ID, APP_AUDIENCE};
+use auth_service::{
+    domain::ErrorResponse, routes::AudienceTokenResponse, utils::TOKEN_TTL_SECONDS,
+};
+
+async fn signup_and_login(app: &TestApp) {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+async fn audience_token(app: &TestApp, audience: &str) -> AudienceTokenResponse {
+    let response = app
+        .post_audience_token(&serde_json::json!({ "audience": audience }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<AudienceTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to AudienceTokenResponse")
+}
+
+async fn verify_token_status(app: &TestApp, body: serde_json::Value) -> u16 {
+    app.post_verify_token(&body).await.status().as_u16()
+}
+
+#[tokio::test]
+async fn should_mint_token_only_its_audience_accepts() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    let response = audience_token(&app, APP_AUDIENCE).await;
+    assert_eq!(response.audience, APP_AUDIENCE);
+    assert_eq!(response.token_type, "Bearer");
+    assert_eq!(response.expires_in, TOKEN_TTL_SECONDS);
+    let token = response.access_token;
+
+    let for_audience = |audience: Option<&str>| match audience {
+        Some(audience) => serde_json::json!({ "token": token, "audience": audience }),
+        None => serde_json::json!({ "token": token }),
+    };
+    assert_eq!(
+        verify_token_status(&app, for_audience(Some(APP_AUDIENCE))).await,
+        200
+    );
+    assert_eq!(
+        verify_token_status(&app, for_audience(Some(API_CLIENT_ID))).await,
+        401
+    );
+    // Nor is it accepted by this service itself
+    assert_eq!(verify_token_status(&app, for_audience(None)).await, 401);
+    let response = app.get_sessions_with_bearer(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_for_unknown_audience() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    let response = app
+        .post_audience_token(&serde_json::json!({ "audience": "unknown-service" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .error,
+        "Invalid audience"
+    );
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_audience_token(&serde_json::json!({ "audience": APP_AUDIENCE }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_invalidate_audience_token_on_logout() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+    let token = audience_token(&app, APP_AUDIENCE).await.access_token;
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = serde_json::json!({ "token": token, "audience": APP_AUDIENCE });
+    assert_eq!(verify_token_status(&app, body).await, 401);
+
+    app.clean_up().await.unwrap();
+}
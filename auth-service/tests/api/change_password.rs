@@ -0,0 +1,271 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{ErrorResponse, RefreshToken, RefreshTokenStore, RefreshTokenStoreError},
+    utils::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
+use reqwest::Url;
+use secrecy::Secret;
+
+async fn signup(app: &TestApp) -> String {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    email
+}
+
+/// Logs in and returns the refresh token of the new session
+async fn login(app: &TestApp, email: &str, password: &str) -> String {
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": password }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let refresh_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+        .expect("No refresh cookie found")
+        .value()
+        .to_owned();
+    refresh_token
+}
+
+async fn current_refresh_token(
+    app: &TestApp,
+    refresh_token: &str,
+) -> Result<RefreshToken, RefreshTokenStoreError> {
+    let refresh_token = RefreshToken::parse(Secret::new(refresh_token.to_owned())).unwrap();
+    let store = app.refresh_token_store.read().await;
+    let family = store.get_family(&refresh_token).await?;
+    store.get_current_token(&family.id).await
+}
+
+fn change_body(current: &str, new: &str) -> serde_json::Value {
+    serde_json::json!({
+        "currentPassword": current,
+        "newPassword": new,
+    })
+}
+
+#[tokio::test]
+async fn should_return_200_and_keep_only_current_session() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    let other_session = login(&app, &email, "Password123!").await;
+    let this_session = login(&app, &email, "Password123!").await;
+
+    let response = app
+        .post_change_password(&change_body("Password123!", "NewPassword456!"))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(
+        current_refresh_token(&app, &other_session).await,
+        Err(RefreshTokenStoreError::TokenNotFound)
+    );
+    assert!(current_refresh_token(&app, &this_session).await.is_ok());
+
+    // The current session can still be refreshed
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    login(&app, &email, "NewPassword456!").await;
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_keep_session_of_jwt_even_if_refresh_cookie_differs() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    let other_session = login(&app, &email, "Password123!").await;
+    let this_session = login(&app, &email, "Password123!").await;
+
+    // A refresh cookie planted from another session must not decide which one survives
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, other_session
+        ),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+
+    let response = app
+        .post_change_password(&change_body("Password123!", "NewPassword456!"))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(
+        current_refresh_token(&app, &other_session).await,
+        Err(RefreshTokenStoreError::TokenNotFound)
+    );
+    assert!(current_refresh_token(&app, &this_session).await.is_ok());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_invalidate_jwts_of_other_sessions() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    let other_jwt = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    login(&app, &email, "Password123!").await;
+
+    let response = app
+        .post_change_password(&change_body("Password123!", "NewPassword456!"))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let new_jwt = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": other_jwt }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The caller was handed a replacement JWT
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": new_jwt }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_current_password_incorrect() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    login(&app, &email, "Password123!").await;
+
+    let response = app
+        .post_change_password(&change_body("WrongPassword123!", "NewPassword456!"))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(error_response.error, "Incorrect credentials");
+
+    // The old password is still in place
+    login(&app, &email, "Password123!").await;
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_if_new_password_invalid() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    login(&app, &email, "Password123!").await;
+
+    for new_password in ["", "short1!", "nouppercase123!", "NoSpecialChar123"] {
+        let response = app
+            .post_change_password(&change_body("Password123!", new_password))
+            .await;
+        assert_eq!(response.status().as_u16(), 400);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_change_password(&change_body("Password123!", "NewPassword456!"))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let mut app = TestApp::new().await;
+
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}=invalid; HttpOnly; SameSite=Lax; Secure; Path=/",
+            JWT_COOKIE_NAME
+        ),
+        &Url::parse("http://127.0.0.1").expect("Failed to parse URL"),
+    );
+
+    let response = app
+        .post_change_password(&change_body("Password123!", "NewPassword456!"))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_after_logout() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    let jwt = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Put the now-banned token back into the jar
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            JWT_COOKIE_NAME, jwt
+        ),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+
+    let response = app
+        .post_change_password(&change_body("Password123!", "NewPassword456!"))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let mut app = TestApp::new().await;
+    // Authentication is checked before the body is parsed
+    let email = signup(&app).await;
+    login(&app, &email, "Password123!").await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({ "newPassword": "NewPassword456!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 422);
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,78 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::routes::ConsentsResponse;
+
+async fn signup_and_login(app: &TestApp) {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_keep_latest_answer_per_purpose() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    let response = app.get_account_consents().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: ConsentsResponse = response.json().await.unwrap();
+    assert!(body.consents.is_empty());
+
+    for (purpose, granted) in [
+        ("newsletter", true),
+        ("analytics", true),
+        ("newsletter", false),
+    ] {
+        let response = app
+            .post_account_consent(&serde_json::json!({ "purpose": purpose, "granted": granted }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let body: ConsentsResponse = app.get_account_consents().await.json().await.unwrap();
+    let answers: Vec<(&str, bool)> = body
+        .consents
+        .iter()
+        .map(|consent| (consent.purpose.as_str(), consent.granted))
+        .collect();
+    assert_eq!(answers, [("analytics", true), ("newsletter", false)]);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_if_purpose_invalid() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    for purpose in ["", "Newsletter", "news letter"] {
+        let response = app
+            .post_account_consent(&serde_json::json!({ "purpose": purpose, "granted": true }))
+            .await;
+        assert_eq!(response.status().as_u16(), 400, "purpose {:?}", purpose);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_require_authentication() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_account_consents().await;
+    assert_eq!(response.status().as_u16(), 400);
+    let response = app
+        .post_account_consent(&serde_json::json!({ "purpose": "newsletter", "granted": true }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
@@ -1,8 +1,16 @@
 use auth_service::{
     app_state::AppState,
+    domain::{
+        AdminKey, ApiClient, ApiClients, Email, RateLimit, RateLimits, TrustedProxies,
+        UnverifiedLoginPolicy, UserId, UserStore,
+    },
     get_postgres_pool, get_redis_client,
-    services::{MockEmailClient, PostgresUserStore, RedisBannedTokenStore, RedisTwoFACodeStore},
-    utils::{test, DATABASE_URL, REDIS_HOST_NAME},
+    services::{
+        MockEmailClient, PostgresAuditEventStore, PostgresConsentStore, PostgresPasskeyStore,
+        PostgresUserStore, RedisBannedTokenStore, RedisMagicLinkStore, RedisOneTimeTokenStore,
+        RedisRateLimitStore, RedisRefreshTokenStore, RedisSessionStore, RedisTwoFACodeStore,
+    },
+    utils::{test, ADMIN_KEY_HEADER, DATABASE_URL, REDIS_HOST_NAME},
     Application,
 };
 use reqwest::cookie::Jar;
@@ -11,25 +19,68 @@ use sqlx::{
     postgres::{PgConnectOptions, PgPoolOptions},
     Connection, Executor, PgConnection, PgPool,
 };
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+/// The API client every test app accepts for server-to-server endpoints
+pub const API_CLIENT_ID: &str = "resource-server";
+pub const API_CLIENT_SECRET: &str = "resource-server-secret";
+
+/// A downstream service every test app mints tokens for, besides the API client
+pub const APP_AUDIENCE: &str = "app-service";
+
+/// The key every test app accepts for the admin API
+pub const ADMIN_KEY: &str = "admin-key";
+
 /// Test application wrapper that provides HTTP client functionality for integration tests.
 /// This struct encapsulates a running server instance and an HTTP client for making requests.
 pub struct TestApp {
     pub address: String,
     pub db_name: String,
+    /// The state the app was built with, for calling background tasks directly
+    pub app_state: AppState,
     pub cookie_jar: Arc<Jar>,
     pub http_client: reqwest::Client,
+    pub user_store: Arc<RwLock<PostgresUserStore>>,
     pub banned_token_store: Arc<RwLock<RedisBannedTokenStore>>,
     pub two_fa_code_store: Arc<RwLock<RedisTwoFACodeStore>>,
+    pub refresh_token_store: Arc<RwLock<RedisRefreshTokenStore>>,
     pub email_client: Arc<MockEmailClient>,
     pub clean_up_called: bool,
 }
 
 impl TestApp {
+    /// Test app that lets unverified accounts log in, so tests needn't verify every signup.
     pub async fn new() -> Self {
+        Self::with_unverified_login_policy(UnverifiedLoginPolicy::Allow).await
+    }
+
+    pub async fn with_unverified_login_policy(policy: UnverifiedLoginPolicy) -> Self {
+        Self::build(policy, unreachable_rate_limits(), TrustedProxies::default()).await
+    }
+
+    /// Test app enforcing the given rate limits; all others never limit anyone.
+    /// The test client stands in for nginx, so its `X-Real-IP` is believed.
+    pub async fn with_rate_limits(rate_limits: RateLimits) -> Self {
+        Self::build(UnverifiedLoginPolicy::Allow, rate_limits, loopback()).await
+    }
+
+    /// Test app believing `X-Real-IP` from the given proxies; all others believe no one.
+    pub async fn with_trusted_proxies(trusted_proxies: TrustedProxies) -> Self {
+        Self::build(
+            UnverifiedLoginPolicy::Allow,
+            unreachable_rate_limits(),
+            trusted_proxies,
+        )
+        .await
+    }
+
+    async fn build(
+        policy: UnverifiedLoginPolicy,
+        rate_limits: RateLimits,
+        trusted_proxies: TrustedProxies,
+    ) -> Self {
         let pg_pool = configure_postgresql().await;
         let db_name = pg_pool
             .connect_options()
@@ -44,20 +95,48 @@ impl TestApp {
                 .expect("Failed to get Redis connection"),
         ));
 
-        let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool)));
+        let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool.clone())));
+        let passkey_store = Arc::new(RwLock::new(PostgresPasskeyStore::new(pg_pool.clone())));
+        let audit_event_store =
+            Arc::new(RwLock::new(PostgresAuditEventStore::new(pg_pool.clone())));
+        let consent_store = Arc::new(RwLock::new(PostgresConsentStore::new(pg_pool)));
         let banned_token_store =
             Arc::new(RwLock::new(RedisBannedTokenStore::new(redis_conn.clone())));
         let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(redis_conn.clone())));
-        let email_client = Arc::new(MockEmailClient);
-        let app_state = AppState::new(
+        let refresh_token_store =
+            Arc::new(RwLock::new(RedisRefreshTokenStore::new(redis_conn.clone())));
+        let magic_link_store = Arc::new(RwLock::new(RedisMagicLinkStore::new(redis_conn.clone())));
+        let session_store = Arc::new(RwLock::new(RedisSessionStore::new(redis_conn.clone())));
+        let one_time_token_store =
+            Arc::new(RwLock::new(RedisOneTimeTokenStore::new(redis_conn.clone())));
+        let rate_limit_store = Arc::new(RwLock::new(RedisRateLimitStore::new(redis_conn.clone())));
+        let email_client = Arc::new(MockEmailClient::default());
+        let mut app_state = AppState::new(
             user_store.clone(),
             banned_token_store.clone(),
             two_fa_code_store.clone(),
+            refresh_token_store.clone(),
+            one_time_token_store,
+            passkey_store,
+            magic_link_store,
+            session_store,
+            rate_limit_store,
+            audit_event_store,
+            consent_store,
             email_client.clone(),
         );
+        app_state.unverified_login_policy = policy;
+        app_state.rate_limits = rate_limits;
+        app_state.trusted_proxies = trusted_proxies;
+        app_state.api_clients = ApiClients::new(vec![ApiClient::new(
+            API_CLIENT_ID.to_owned(),
+            &Secret::new(API_CLIENT_SECRET.to_owned()),
+        )]);
+        app_state.token_audiences = vec![API_CLIENT_ID.to_owned(), APP_AUDIENCE.to_owned()];
+        app_state.admin_key = Some(AdminKey::new(&Secret::new(ADMIN_KEY.to_owned())));
 
         // Build application on random port for test isolation
-        let app = Application::build(app_state, test::APP_ADDRESS)
+        let app = Application::build(app_state.clone(), test::APP_ADDRESS)
             .await
             .expect("Failed to build app");
 
@@ -77,10 +156,13 @@ impl TestApp {
         TestApp {
             address,
             db_name,
+            app_state,
             cookie_jar,
             http_client,
+            user_store,
             banned_token_store,
             two_fa_code_store,
+            refresh_token_store,
             email_client,
             clean_up_called: false,
         }
@@ -96,7 +178,25 @@ impl TestApp {
     /// Makes a GET request to the root endpoint ("/")
     pub async fn get_root(&self) -> reqwest::Response {
         self.http_client
-            .get(&format!("{}/", &self.address))
+            .get(format!("{}/", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Opens the page the password reset link points at
+    pub async fn get_password_reset_page(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/password-reset?token={}", &self.address, token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the JWKS endpoint
+    pub async fn get_jwks(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/.well-known/jwks.json", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -108,7 +208,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/signup", &self.address))
+            .post(format!("{}/signup", &self.address))
             .json(body)
             .send()
             .await
@@ -121,7 +221,20 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the login endpoint that returns tokens in the body
+    pub async fn post_login_token<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/login/token", &self.address))
             .json(body)
             .send()
             .await
@@ -131,7 +244,67 @@ impl TestApp {
     /// Makes a POST request to the logout endpoint (no body required)
     pub async fn post_logout(&self) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/logout", &self.address))
+            .post(format!("{}/logout", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the logout endpoint with a bearer token
+    pub async fn post_logout_with_bearer(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/logout", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the logout-all endpoint (JWT travels in the cookie jar)
+    pub async fn post_logout_all(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/logout-all", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the change-password endpoint (JWT travels in the cookie jar)
+    pub async fn post_change_password<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/change-password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the sessions endpoint (JWT travels in the cookie jar)
+    pub async fn get_sessions(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/sessions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the session listing endpoint with a bearer token
+    pub async fn get_sessions_with_bearer(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/sessions", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to revoke one of the user's sessions
+    pub async fn post_revoke_session(&self, session_id: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/sessions/{}/revoke", &self.address, session_id))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -150,13 +323,469 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    /// Makes a form POST request to the introspection endpoint with the given client credentials
+    pub async fn post_introspect<Body>(
+        &self,
+        body: &Body,
+        client: Option<(&str, &str)>,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let request = self
+            .http_client
+            .post(format!("{}/introspect", &self.address))
+            .form(body);
+        let request = match client {
+            Some((id, secret)) => request.basic_auth(id, Some(secret)),
+            None => request,
+        };
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    /// Makes a form POST request to the token revocation endpoint
+    pub async fn post_revoke<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/revoke", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the refresh endpoint (refresh token travels in the cookie jar)
+    pub async fn post_refresh_token(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/token/refresh", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the refresh endpoint with the refresh token in the body
+    pub async fn post_refresh_token_body<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/token/refresh", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to mint a token for a downstream audience
+    pub async fn post_audience_token<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/token/audience", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request for an export of the caller's personal data
+    pub async fn get_account_export(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/export", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request for the caller's consent answers
+    pub async fn get_account_consents(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/consents", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request recording the caller's answer for one consent purpose
+    pub async fn post_account_consent<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/consents", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request asking to move the caller's account to another address
+    pub async fn post_account_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request following the link sent to the new address of an email change
+    pub async fn get_account_email_confirm(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/email/confirm", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request following the link sent to the old address of an email change
+    pub async fn get_account_email_cancel(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/email/cancel", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request asking for the caller's account to be deleted
+    pub async fn post_account_delete<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/delete", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the account deletion cancellation link
+    pub async fn get_account_delete_cancel(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/delete/cancel", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the admin role listing endpoint
+    pub async fn get_admin_roles(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/admin/roles", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the admin user listing endpoint
+    pub async fn get_admin_users(
+        &self,
+        query: &[(&str, &str)],
+        admin_key: Option<&str>,
+    ) -> reqwest::Response {
+        let request = self
+            .http_client
+            .get(format!("{}/admin/users", &self.address))
+            .query(query);
+        with_admin_key(request, admin_key)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request for one user through the admin API
+    pub async fn get_admin_user(
+        &self,
+        user_id: &UserId,
+        admin_key: Option<&str>,
+    ) -> reqwest::Response {
+        let request = self
+            .http_client
+            .get(format!("{}/admin/users/{}", &self.address, user_id));
+        with_admin_key(request, admin_key)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to an admin user action, e.g. `disable`
+    pub async fn post_admin_user_action(
+        &self,
+        user_id: &UserId,
+        action: &str,
+        admin_key: Option<&str>,
+    ) -> reqwest::Response {
+        let request = self.http_client.post(format!(
+            "{}/admin/users/{}/{}",
+            &self.address, user_id, action
+        ));
+        with_admin_key(request, admin_key)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request setting whether a user must pass 2FA
+    pub async fn post_admin_requires_2fa<Body>(
+        &self,
+        user_id: &UserId,
+        body: &Body,
+        admin_key: Option<&str>,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let request = self
+            .http_client
+            .post(format!(
+                "{}/admin/users/{}/requires-2fa",
+                &self.address, user_id
+            ))
+            .json(body);
+        with_admin_key(request, admin_key)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a DELETE request for a user through the admin API
+    pub async fn delete_admin_user(
+        &self,
+        user_id: &UserId,
+        admin_key: Option<&str>,
+    ) -> reqwest::Response {
+        let request = self
+            .http_client
+            .delete(format!("{}/admin/users/{}", &self.address, user_id));
+        with_admin_key(request, admin_key)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the password reset request endpoint
+    pub async fn post_password_reset_request<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/request", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the password reset confirmation endpoint
+    pub async fn post_password_reset_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Pulls the `token=` query value out of the most recent email sent to `email`
+    pub fn get_emailed_token(&self, email: &str) -> Option<String> {
+        self.email_client
+            .sent_emails()
+            .iter()
+            .rev()
+            .find(|sent| sent.recipient.as_ref().expose_secret() == email)
+            .and_then(|sent| {
+                sent.content.split("token=").nth(1).map(|rest| {
+                    // Signed tokens carry a base64url signature after a dot
+                    rest.chars()
+                        .take_while(|c| c.is_ascii_alphanumeric() || "-_.".contains(*c))
+                        .collect()
+                })
+            })
+    }
+
+    /// Id of the user going by `email`, for calling the stores directly
+    pub async fn get_user_id(&self, email: &str) -> UserId {
+        let email = Email::parse(Secret::new(email.to_owned())).unwrap();
+        self.user_store
+            .read()
+            .await
+            .get_user_by_email(&email)
+            .await
+            .expect("User should exist")
+            .id
+    }
+
+    /// Makes a POST request asking for a login link by email
+    pub async fn post_magic_link<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/login/magic-link", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the magic link consume endpoint, as the emailed link would
+    pub async fn get_magic_link_consume(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/login/magic-link/consume", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to start TOTP enrollment (JWT travels in the cookie jar)
+    pub async fn post_totp_enroll(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/2fa/totp/enroll", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to confirm TOTP enrollment with a code from the app
+    pub async fn post_totp_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/2fa/totp/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request for the number of unused recovery codes
+    pub async fn get_recovery_codes_status(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/2fa/recovery-codes", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to replace the recovery codes with a new set
+    pub async fn post_regenerate_recovery_codes<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/2fa/recovery-codes/regenerate", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request for passkey registration options (JWT travels in the cookie jar)
+    pub async fn post_passkey_register_start(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/passkey/register/start", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request with the authenticator's response to a registration challenge
+    pub async fn post_passkey_register_finish<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkey/register/finish", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request for passkey login options
+    pub async fn post_passkey_login_start<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkey/login/start", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request with the authenticator's assertion to complete a passkey login
+    pub async fn post_passkey_login_finish<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkey/login/finish", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a GET request to the email verification endpoint, as the emailed link would
+    pub async fn get_verify_email(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/verify-email", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the endpoint that sends another verification link
+    pub async fn post_resend_verification<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/verify-email/resend", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     /// Makes a POST request to the 2FA verification endpoint with a token
     pub async fn post_verify_2fa<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/verify-2fa", &self.address))
+            .post(format!("{}/verify-2fa", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Makes a POST request to the 2FA verification endpoint that returns tokens in the body
+    pub async fn post_verify_2fa_token<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/verify-2fa/token", &self.address))
             .json(body)
             .send()
             .await
@@ -174,6 +803,35 @@ impl Drop for TestApp {
     }
 }
 
+/// Adds the admin key header if one is given; otherwise the cookie authenticates the request
+fn with_admin_key(
+    request: reqwest::RequestBuilder,
+    admin_key: Option<&str>,
+) -> reqwest::RequestBuilder {
+    match admin_key {
+        Some(key) => request.header(ADMIN_KEY_HEADER, key),
+        None => request,
+    }
+}
+
+// Every test app is reached from the same address, so limits are only enforced where asked for
+fn unreachable_rate_limits() -> RateLimits {
+    let unlimited = RateLimit::new(u32::MAX, Duration::from_secs(60));
+    let mut rate_limits = RateLimits::default();
+    for limit in [&mut rate_limits.login, &mut rate_limits.verify_2fa] {
+        limit.per_ip = unlimited;
+        limit.per_email = unlimited;
+    }
+    rate_limits
+}
+
+/// Trust in the address every test client connects from.
+pub fn loopback() -> TrustedProxies {
+    "127.0.0.1,::1"
+        .parse()
+        .expect("Failed to parse loopback addresses")
+}
+
 pub fn get_random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
@@ -184,7 +842,7 @@ async fn configure_postgresql() -> PgPool {
     // We are creating a new database for each test case, and we need to ensure each database has a unique name!
     let db_name = Uuid::new_v4().to_string();
 
-    configure_database(&postgresql_conn_url.expose_secret(), &db_name).await;
+    configure_database(postgresql_conn_url.expose_secret(), &db_name).await;
 
     let postgresql_conn_url_with_db =
         format!("{}/{}", postgresql_conn_url.expose_secret(), db_name);
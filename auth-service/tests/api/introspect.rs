@@ -0,0 +1,155 @@
+use crate::helpers::{get_random_email, TestApp, API_CLIENT_ID, API_CLIENT_SECRET, APP_AUDIENCE};
+use auth_service::{
+    domain::ErrorResponse,
+    routes::{AudienceTokenResponse, IntrospectResponse},
+    utils::{JWT_AUDIENCE, JWT_COOKIE_NAME, JWT_ISSUER},
+};
+
+const CLIENT: Option<(&str, &str)> = Some((API_CLIENT_ID, API_CLIENT_SECRET));
+
+/// Signs up and logs in, returning the email and the issued JWT
+async fn signup_and_login(app: &TestApp) -> (String, String) {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let jwt = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    (email, jwt)
+}
+
+async fn introspect(app: &TestApp, token: &str) -> serde_json::Value {
+    let response = app.post_introspect(&[("token", token)], CLIENT).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json()
+        .await
+        .expect("Could not deserialize introspection response")
+}
+
+#[tokio::test]
+async fn should_describe_active_token() {
+    let mut app = TestApp::new().await;
+    let (email, jwt) = signup_and_login(&app).await;
+
+    let response = app
+        .post_introspect(
+            &[("token", jwt.as_str()), ("token_type_hint", "access_token")],
+            CLIENT,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response
+        .json::<IntrospectResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectResponse");
+
+    assert!(body.active);
+    let user_id = app.get_user_id(&email).await;
+    assert_eq!(body.sub, Some(user_id.to_string()));
+    assert_eq!(body.iss.as_deref(), Some(JWT_ISSUER.as_str()));
+    assert_eq!(body.aud.as_deref(), Some(JWT_AUDIENCE.as_str()));
+    let (iat, exp) = (body.iat.unwrap(), body.exp.unwrap());
+    assert!(iat < exp);
+    assert_eq!(body.nbf, Some(iat));
+
+    // Tokens don't record a client, so none is claimed
+    let body = introspect(&app, &jwt).await;
+    assert!(body.get("client_id").is_none());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_report_only_inactive_for_invalid_tokens() {
+    let mut app = TestApp::new().await;
+    let (_, jwt) = signup_and_login(&app).await;
+
+    let body = introspect(&app, "not-a-token").await;
+    assert_eq!(body, serde_json::json!({ "active": false }));
+
+    // A logged-out token is no longer active
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = introspect(&app, &jwt).await;
+    assert_eq!(body, serde_json::json!({ "active": false }));
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_without_valid_client_credentials() {
+    let mut app = TestApp::new().await;
+    let (_, jwt) = signup_and_login(&app).await;
+
+    for client in [
+        None,
+        Some((API_CLIENT_ID, "wrong-secret")),
+        Some(("unknown-client", API_CLIENT_SECRET)),
+    ] {
+        let response = app
+            .post_introspect(&[("token", jwt.as_str())], client)
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(
+            response
+                .headers()
+                .get("www-authenticate")
+                .and_then(|value| value.to_str().ok()),
+            Some("Basic")
+        );
+        assert_eq!(
+            response
+                .json::<ErrorResponse>()
+                .await
+                .expect("Could not deserialize response body to ErrorResponse")
+                .error,
+            "Invalid client"
+        );
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+async fn audience_token(app: &TestApp, audience: &str) -> String {
+    let response = app
+        .post_audience_token(&serde_json::json!({ "audience": audience }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<AudienceTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to AudienceTokenResponse")
+        .access_token
+}
+
+#[tokio::test]
+async fn should_only_describe_audience_tokens_to_their_audience() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    let own_token = audience_token(&app, API_CLIENT_ID).await;
+    let body = introspect(&app, &own_token).await;
+    assert_eq!(body["active"], true);
+    assert_eq!(body["aud"], API_CLIENT_ID);
+
+    // A token minted for another downstream service can't be replayed here
+    let other_token = audience_token(&app, APP_AUDIENCE).await;
+    let body = introspect(&app, &other_token).await;
+    assert_eq!(body, serde_json::json!({ "active": false }));
+
+    app.clean_up().await.unwrap();
+}
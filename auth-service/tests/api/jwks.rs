@@ -0,0 +1,79 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::utils::{Claims, JWT_AUDIENCE, JWT_COOKIE_NAME, JWT_ISSUER};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+
+#[tokio::test]
+async fn should_return_200_with_public_signing_key() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_jwks().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jwks = response
+        .json::<JwkSet>()
+        .await
+        .expect("Could not deserialize response body to JwkSet");
+
+    assert!(!jwks.keys.is_empty());
+    for jwk in jwks.keys.iter() {
+        assert!(jwk.common.key_id.is_some(), "Every key must carry a kid");
+        assert!(jwk.is_supported());
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_verify_issued_token_with_published_key() {
+    let mut app = TestApp::new().await;
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    app.post_signup(&signup_body).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "Password123!"
+    });
+    let login_response = app.post_login(&login_body).await;
+    let token = login_response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+
+    let jwks = app
+        .get_jwks()
+        .await
+        .json::<JwkSet>()
+        .await
+        .expect("Could not deserialize response body to JwkSet");
+
+    // Verify the way a downstream service would: pick the key by kid, no shared secret needed
+    let kid = decode_header(&token)
+        .expect("Token header should decode")
+        .kid
+        .expect("Token should carry a kid");
+    let jwk = jwks.find(&kid).expect("Signing key should be published");
+    let decoding_key = DecodingKey::from_jwk(jwk).expect("Published key should be usable");
+
+    // ...and insist on the issuer and its own audience
+    let mut validation = Validation::new(Algorithm::EdDSA);
+    validation.set_issuer(&[JWT_ISSUER.as_str()]);
+    validation.set_audience(&[JWT_AUDIENCE.as_str()]);
+    let claims = decode::<Claims>(&token, &decoding_key, &validation)
+        .expect("Token should verify against published key")
+        .claims;
+    let user_id = app.get_user_id(&random_email).await;
+    assert_eq!(claims.sub, user_id.to_string());
+
+    validation.set_audience(&["other-service"]);
+    assert!(decode::<Claims>(&token, &decoding_key, &validation).is_err());
+
+    app.clean_up().await.unwrap();
+}
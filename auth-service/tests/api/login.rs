@@ -1,5 +1,8 @@
 use crate::helpers::{get_random_email, TestApp};
-use auth_service::{domain::ErrorResponse, utils::JWT_COOKIE_NAME};
+use auth_service::{
+    domain::ErrorResponse,
+    utils::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
 
 #[tokio::test]
 async fn should_return_422_if_malformed_credentials() {
@@ -130,6 +133,38 @@ async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
 
     assert!(!auth_cookie.value().is_empty());
 
+    let refresh_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+        .expect("No refresh cookie found");
+
+    assert!(!refresh_cookie.value().is_empty());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_200_if_email_case_differs_from_signup() {
+    let mut app = TestApp::new().await;
+    let random_email = get_random_email();
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": random_email.to_uppercase(),
+            "password": "Password123!",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": format!("  {}", random_email),
+            "password": "Password123!",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
     app.clean_up().await.unwrap();
 }
 
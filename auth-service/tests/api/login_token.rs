@@ -0,0 +1,190 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{TwoFACode, TwoFACodeStore},
+    routes::{SessionsResponse, TokenResponse, TwoFactorAuthResponse},
+    utils::TOKEN_TTL_SECONDS,
+};
+
+async fn signup(app: &TestApp, requires_2fa: bool) -> String {
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "Password123!",
+        "requires2FA": requires_2fa
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    random_email
+}
+
+async fn login_token(app: &TestApp, email: &str) -> TokenResponse {
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    });
+    let response = app.post_login_token(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.cookies().count(), 0, "Expected no cookies");
+    response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+}
+
+#[tokio::test]
+async fn should_return_tokens_in_body() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app, false).await;
+
+    let tokens = login_token(&app, &email).await;
+    assert_eq!(tokens.token_type, "Bearer");
+    assert_eq!(tokens.expires_in, TOKEN_TTL_SECONDS);
+    assert!(!tokens.access_token.is_empty());
+    assert!(!tokens.refresh_token.is_empty());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_credentials() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app, false).await;
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "WrongPassword123!",
+    });
+    let response = app.post_login_token(&login_body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_accept_access_token_as_bearer() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    let tokens = login_token(&app, &email).await;
+
+    // Without a cookie the request is unauthenticated
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.get_sessions_with_bearer(&tokens.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+
+    let response = app.get_sessions_with_bearer("invalid").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_prefer_bearer_token_over_cookie() {
+    let mut app = TestApp::new().await;
+    let cookie_email = signup(&app, false).await;
+    let bearer_email = signup(&app, false).await;
+
+    let login_body = serde_json::json!({
+        "email": cookie_email,
+        "password": "Password123!",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let tokens = login_token(&app, &bearer_email).await;
+
+    // The cookie user has a session too, but only the bearer user's is listed
+    let response = app.get_sessions_with_bearer(&tokens.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+
+    // An invalid bearer token is rejected rather than falling back to the cookie
+    let response = app.get_sessions_with_bearer("invalid").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_refresh_with_token_in_body() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    let tokens = login_token(&app, &email).await;
+
+    let body = serde_json::json!({ "refreshToken": tokens.refresh_token });
+    let response = app.post_refresh_token_body(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.cookies().count(), 0, "Expected no cookies");
+    let refreshed = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert_ne!(refreshed.refresh_token, tokens.refresh_token);
+
+    let response = app.get_sessions_with_bearer(&refreshed.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The presented refresh token is spent
+    let response = app.post_refresh_token_body(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_tokens_after_2fa() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app, true).await;
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    });
+    let response = app.post_login_token(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+    let login_attempt_id = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse")
+        .login_attempt_id;
+
+    let user_id = app.get_user_id(&email).await;
+    let (_, code): (_, TwoFACode) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&user_id)
+        .await
+        .expect("Failed to get 2FA code");
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": code.as_ref(),
+    });
+    let response = app.post_verify_2fa_token(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.cookies().count(), 0, "Expected no cookies");
+    let tokens = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+
+    let response = app.get_sessions_with_bearer(&tokens.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
@@ -1,5 +1,9 @@
 use auth_service::domain::BannedTokenStore;
-use auth_service::{domain::ErrorResponse, utils::JWT_COOKIE_NAME};
+use auth_service::{
+    domain::ErrorResponse,
+    routes::TokenResponse,
+    utils::{decode_claims, JWT_COOKIE_NAME},
+};
 use reqwest::cookie::CookieStore;
 use reqwest::Url;
 
@@ -45,12 +49,13 @@ async fn should_return_200_and_ban_jwt_token_if_valid_jwt_cookie() {
     let response = app.post_logout().await;
     assert_eq!(response.status(), 200);
 
-    // Verify token was banned
+    // Verify token was banned by its id
+    let jti = decode_claims(&jwt_token).expect("Failed to decode JWT").jti;
     let is_banned = app
         .banned_token_store
         .read()
         .await
-        .is_token_banned(&jwt_token)
+        .is_token_banned(&jti)
         .await
         .expect("Failed to check banned token");
 
@@ -59,6 +64,41 @@ async fn should_return_200_and_ban_jwt_token_if_valid_jwt_cookie() {
     app.clean_up().await.unwrap();
 }
 
+#[tokio::test]
+async fn should_return_200_and_end_session_if_valid_bearer_token() {
+    let mut app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": random_email,
+            "password": "Password123!",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login_token(&serde_json::json!({
+            "email": random_email,
+            "password": "Password123!",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let tokens = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+
+    let response = app.post_logout_with_bearer(&tokens.access_token).await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.get_sessions_with_bearer(&tokens.access_token).await;
+    assert_eq!(response.status(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
 #[tokio::test]
 async fn should_return_400_if_logout_called_twice_in_a_row() {
     let mut app = TestApp::new().await;
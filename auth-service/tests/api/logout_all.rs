@@ -0,0 +1,114 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::utils::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME};
+use reqwest::Url;
+
+async fn signup(app: &TestApp) -> String {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    email
+}
+
+/// Logs in and returns the JWT and refresh token of the new session
+async fn login(app: &TestApp, email: &str) -> (String, String) {
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let cookie = |name: &str| {
+        response
+            .cookies()
+            .find(|cookie| cookie.name() == name)
+            .expect("Cookie not found")
+            .value()
+            .to_owned()
+    };
+    (cookie(JWT_COOKIE_NAME), cookie(REFRESH_TOKEN_COOKIE_NAME))
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let mut app = TestApp::new().await;
+
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}=invalid; HttpOnly; SameSite=Lax; Path=/",
+            JWT_COOKIE_NAME
+        ),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_invalidate_every_token_of_the_user() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    let sessions = [login(&app, &email).await, login(&app, &email).await];
+
+    let other_email = signup(&app).await;
+    let (other_jwt, _) = login(&app, &other_email).await;
+
+    // Log out everywhere from the second of the user's sessions
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            JWT_COOKIE_NAME, sessions[1].0
+        ),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+    let response = app.post_logout_all().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    for (jwt, refresh_token) in &sessions {
+        let response = app
+            .post_verify_token(&serde_json::json!({ "token": jwt }))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+
+        app.cookie_jar.add_cookie_str(
+            &format!(
+                "{}={}; HttpOnly; SameSite=Lax; Path=/",
+                REFRESH_TOKEN_COOKIE_NAME, refresh_token
+            ),
+            &app.address.parse::<Url>().expect("Failed to parse URL"),
+        );
+        let response = app.post_refresh_token().await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // Other users are unaffected
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": other_jwt }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Logging in again issues tokens that are accepted
+    let (jwt, _) = login(&app, &email).await;
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": jwt }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,171 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::UnverifiedLoginPolicy,
+    routes::TwoFactorAuthResponse,
+    utils::{JWT_COOKIE_NAME, MAGIC_LINK_NONCE_COOKIE_NAME},
+};
+
+async fn signup(app: &TestApp, email: &str, requires_2fa: bool) {
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": requires_2fa
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+/// Ask for a link from the test app's browser and return the token emailed for it.
+async fn request_link(app: &TestApp, email: &str) -> String {
+    let response = app
+        .post_magic_link(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == MAGIC_LINK_NONCE_COOKIE_NAME));
+
+    app.get_emailed_token(email).expect("No login link sent")
+}
+
+#[tokio::test]
+async fn should_return_200_without_sending_email_for_unknown_account() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_magic_link(&serde_json::json!({ "email": get_random_email() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == MAGIC_LINK_NONCE_COOKIE_NAME));
+    assert!(app.email_client.sent_emails().is_empty());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_log_in_with_emailed_link() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, false).await;
+
+    let token = request_link(&app, &email).await;
+    let sent = app.email_client.sent_emails();
+    assert!(sent
+        .last()
+        .unwrap()
+        .content
+        .contains("/login/magic-link/consume?token="));
+
+    let response = app.get_magic_link_consume(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    assert!(!auth_cookie.value().is_empty());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_link_reused() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, false).await;
+
+    let token = request_link(&app, &email).await;
+    let response = app.get_magic_link_consume(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_magic_link_consume(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_only_accept_link_in_requesting_browser() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, false).await;
+    let token = request_link(&app, &email).await;
+
+    // Someone else opening the link has no nonce cookie
+    let other_browser = reqwest::Client::new();
+    let response = other_browser
+        .get(format!("{}/login/magic-link/consume", &app.address))
+        .query(&[("token", &token)])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The failed attempt doesn't burn the link for its owner
+    let response = app.get_magic_link_consume(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_for_tampered_link() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, false).await;
+    let token = request_link(&app, &email).await;
+
+    let (bare, _) = token.split_once('.').unwrap();
+    for tampered in [
+        bare.to_owned(),
+        format!("{}.AAAA", bare),
+        "invalid".to_owned(),
+    ] {
+        let response = app.get_magic_link_consume(&tampered).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_still_require_2fa() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email, true).await;
+    let token = request_link(&app, &email).await;
+
+    let response = app.get_magic_link_consume(&token).await;
+    assert_eq!(response.status().as_u16(), 206);
+    assert!(!response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME));
+    let two_fa: TwoFactorAuthResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(two_fa.message, "2FA required");
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_verify_email_of_unverified_account() {
+    let mut app = TestApp::with_unverified_login_policy(UnverifiedLoginPolicy::Deny).await;
+    let email = get_random_email();
+    signup(&app, &email, false).await;
+    let token = request_link(&app, &email).await;
+
+    let response = app.get_magic_link_consume(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
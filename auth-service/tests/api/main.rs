@@ -1,7 +1,29 @@
+mod account_deletion;
+mod account_email;
+mod account_export;
+mod admin;
+mod audience_token;
+mod change_password;
+mod consents;
 mod helpers;
+mod introspect;
+mod jwks;
 mod login;
+mod login_token;
 mod logout;
+mod logout_all;
+mod magic_link;
+mod passkey;
+mod password_reset;
+mod rate_limit;
+mod recovery_codes;
+mod refresh_token;
+mod revoke;
+mod roles;
 mod root;
+mod sessions;
 mod signup;
+mod totp;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
@@ -0,0 +1,385 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    routes::{PasskeyLoginOptions, PasskeyRegistrationOptions},
+    utils::{JWT_COOKIE_NAME, WEBAUTHN_ORIGIN, WEBAUTHN_RP_ID},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::Value;
+use ring::{
+    digest,
+    rand::{SecureRandom, SystemRandom},
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING},
+};
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+/// ES256 authenticator in software, producing what a browser would relay from a security key.
+struct SoftwareAuthenticator {
+    key_pair: EcdsaKeyPair,
+    credential_id: Vec<u8>,
+    sign_count: u32,
+    origin: String,
+}
+
+impl SoftwareAuthenticator {
+    fn new() -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                .unwrap();
+        let mut credential_id = vec![0; 16];
+        rng.fill(&mut credential_id).unwrap();
+
+        Self {
+            key_pair,
+            credential_id,
+            sign_count: 0,
+            origin: WEBAUTHN_ORIGIN.to_owned(),
+        }
+    }
+
+    fn credential_id(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.credential_id)
+    }
+
+    fn client_data(&self, ceremony: &str, challenge: &str) -> Vec<u8> {
+        serde_json::json!({
+            "type": ceremony,
+            "challenge": challenge,
+            "origin": self.origin,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn authenticator_data(&self, flags: u8) -> Vec<u8> {
+        let mut data = digest::digest(&digest::SHA256, WEBAUTHN_RP_ID.as_bytes())
+            .as_ref()
+            .to_vec();
+        data.push(flags);
+        data.extend_from_slice(&self.sign_count.to_be_bytes());
+        data
+    }
+
+    fn cose_key(&self) -> Vec<u8> {
+        // Uncompressed SEC1 point: 0x04 || x || y
+        let point = self.key_pair.public_key().as_ref();
+        let key = Value::Map(vec![
+            (Value::Integer(1.into()), Value::Integer(2.into())),
+            (Value::Integer(3.into()), Value::Integer((-7).into())),
+            (Value::Integer((-1).into()), Value::Integer(1.into())),
+            (
+                Value::Integer((-2).into()),
+                Value::Bytes(point[1..33].to_vec()),
+            ),
+            (
+                Value::Integer((-3).into()),
+                Value::Bytes(point[33..].to_vec()),
+            ),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&key, &mut bytes).unwrap();
+        bytes
+    }
+
+    /// Answer `navigator.credentials.create()`.
+    fn register(&self, options: &PasskeyRegistrationOptions) -> serde_json::Value {
+        let mut auth_data = self.authenticator_data(
+            FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL_DATA,
+        );
+        auth_data.extend_from_slice(&[0; 16]); // aaguid
+        auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+        auth_data.extend_from_slice(&self.credential_id);
+        auth_data.extend_from_slice(&self.cose_key());
+
+        let attestation_object = Value::Map(vec![
+            (Value::Text("fmt".into()), Value::Text("none".into())),
+            (Value::Text("attStmt".into()), Value::Map(vec![])),
+            (Value::Text("authData".into()), Value::Bytes(auth_data)),
+        ]);
+        let mut attestation_bytes = Vec::new();
+        ciborium::into_writer(&attestation_object, &mut attestation_bytes).unwrap();
+
+        serde_json::json!({
+            "id": self.credential_id(),
+            "rawId": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(
+                    self.client_data("webauthn.create", &options.public_key.challenge)
+                ),
+                "attestationObject": URL_SAFE_NO_PAD.encode(attestation_bytes),
+            },
+        })
+    }
+
+    /// Answer `navigator.credentials.get()`, advancing the signature counter.
+    fn assert(&mut self, options: &PasskeyLoginOptions, flags: u8) -> serde_json::Value {
+        self.sign_count += 1;
+        let auth_data = self.authenticator_data(flags);
+        let client_data = self.client_data("webauthn.get", &options.public_key.challenge);
+
+        let client_data_hash = digest::digest(&digest::SHA256, &client_data);
+        let signed = [auth_data.as_slice(), client_data_hash.as_ref()].concat();
+        let signature = self.key_pair.sign(&SystemRandom::new(), &signed).unwrap();
+
+        serde_json::json!({
+            "id": self.credential_id(),
+            "rawId": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(client_data),
+                "authenticatorData": URL_SAFE_NO_PAD.encode(auth_data),
+                "signature": URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            },
+        })
+    }
+}
+
+async fn signup_and_login(app: &TestApp) -> String {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    email
+}
+
+async fn registration_options(app: &TestApp) -> PasskeyRegistrationOptions {
+    let response = app.post_passkey_register_start().await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json()
+        .await
+        .expect("Failed to parse response body")
+}
+
+async fn login_options(app: &TestApp, email: &str) -> PasskeyLoginOptions {
+    let response = app
+        .post_passkey_login_start(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json()
+        .await
+        .expect("Failed to parse response body")
+}
+
+/// Sign up, register a passkey, then log out so the next login has to use it.
+async fn register_passkey(app: &TestApp) -> (String, SoftwareAuthenticator) {
+    let email = signup_and_login(app).await;
+    let authenticator = SoftwareAuthenticator::new();
+
+    let options = registration_options(app).await;
+    let response = app
+        .post_passkey_register_finish(&authenticator.register(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+    (email, authenticator)
+}
+
+#[tokio::test]
+async fn register_should_return_400_if_jwt_cookie_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app.post_passkey_register_start().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn register_start_should_describe_the_relying_party_and_user() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+
+    let options = registration_options(&app).await.public_key;
+    assert_eq!(options.rp.id, WEBAUTHN_RP_ID.as_str());
+    assert_eq!(options.user.name, email);
+    assert_ne!(options.user.id, email);
+    assert_eq!(
+        options.authenticator_selection.user_verification,
+        "required"
+    );
+    assert_eq!(options.attestation, "none");
+    assert!(options.exclude_credentials.is_empty());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_login_with_registered_passkey() {
+    let mut app = TestApp::new().await;
+    let (email, mut authenticator) = register_passkey(&app).await;
+
+    let options = login_options(&app, &email).await;
+    assert_eq!(options.public_key.rp_id, WEBAUTHN_RP_ID.as_str());
+    assert_eq!(options.public_key.allow_credentials.len(), 1);
+    assert_eq!(
+        options.public_key.allow_credentials[0].id,
+        authenticator.credential_id()
+    );
+
+    let body = authenticator.assert(&options, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    assert!(!auth_cookie.value().is_empty());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn register_start_should_exclude_existing_passkeys() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+    let authenticator = SoftwareAuthenticator::new();
+    let options = registration_options(&app).await;
+    let response = app
+        .post_passkey_register_finish(&authenticator.register(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let options = registration_options(&app).await;
+    assert_eq!(options.public_key.exclude_credentials.len(), 1);
+
+    // Registering the same credential again is refused
+    let response = app
+        .post_passkey_register_finish(&authenticator.register(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn register_should_return_400_for_wrong_origin() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    let mut authenticator = SoftwareAuthenticator::new();
+    authenticator.origin = "https://phishing.example".to_owned();
+    let options = registration_options(&app).await;
+    let response = app
+        .post_passkey_register_finish(&authenticator.register(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn login_should_return_401_if_challenge_reused() {
+    let mut app = TestApp::new().await;
+    let (email, mut authenticator) = register_passkey(&app).await;
+
+    let options = login_options(&app, &email).await;
+    let body = authenticator.assert(&options, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = authenticator.assert(&options, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn login_should_return_401_for_bad_signature() {
+    let mut app = TestApp::new().await;
+    let (email, mut authenticator) = register_passkey(&app).await;
+
+    let options = login_options(&app, &email).await;
+    let mut body = authenticator.assert(&options, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+    // Signed by a key the server has never seen
+    let forged = SoftwareAuthenticator::new()
+        .key_pair
+        .sign(&SystemRandom::new(), b"forged")
+        .unwrap();
+    body["response"]["signature"] = URL_SAFE_NO_PAD.encode(forged.as_ref()).into();
+
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn login_should_return_401_without_user_verification() {
+    let mut app = TestApp::new().await;
+    let (email, mut authenticator) = register_passkey(&app).await;
+
+    let options = login_options(&app, &email).await;
+    let body = authenticator.assert(&options, FLAG_USER_PRESENT);
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn login_should_return_401_if_sign_count_does_not_advance() {
+    let mut app = TestApp::new().await;
+    let (email, mut authenticator) = register_passkey(&app).await;
+
+    let options = login_options(&app, &email).await;
+    let body = authenticator.assert(&options, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // A clone of the authenticator would still be at the old counter
+    authenticator.sign_count -= 1;
+    let options = login_options(&app, &email).await;
+    let body = authenticator.assert(&options, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn login_should_return_401_for_another_users_passkey() {
+    let mut app = TestApp::new().await;
+    let (_, mut authenticator) = register_passkey(&app).await;
+    let (other_email, _) = register_passkey(&app).await;
+
+    let options = login_options(&app, &other_email).await;
+    let body = authenticator.assert(&options, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+    let response = app.post_passkey_login_finish(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn login_start_should_return_400_for_invalid_email() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_passkey_login_start(&serde_json::json!({ "email": "not-an-email" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,240 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{ErrorResponse, RefreshToken, RefreshTokenStore, RefreshTokenStoreError},
+    utils::REFRESH_TOKEN_COOKIE_NAME,
+};
+use secrecy::Secret;
+
+async fn signup_and_login(app: &TestApp, email: &str) -> String {
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let refresh_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+        .expect("No refresh cookie found")
+        .value()
+        .to_owned();
+    refresh_token
+}
+
+async fn request_reset_token(app: &TestApp, email: &str) -> String {
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.get_emailed_token(email)
+        .expect("No password reset email was sent")
+}
+
+#[tokio::test]
+async fn request_should_return_422_if_malformed_input() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "mail": "nope" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 422);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn request_should_return_200_without_email_for_unknown_account() {
+    let mut app = TestApp::new().await;
+
+    for email in [get_random_email(), "not-an-email".to_owned()] {
+        let response = app
+            .post_password_reset_request(&serde_json::json!({ "email": email }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert!(app.email_client.sent_emails().is_empty());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn request_should_email_reset_link_to_existing_user() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup_and_login(&app, &email).await;
+
+    let token = request_reset_token(&app, &email).await;
+    assert_eq!(token.len(), 43);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn reset_link_should_open_the_reset_page() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup_and_login(&app, &email).await;
+
+    let token = request_reset_token(&app, &email).await;
+    let sent = app.email_client.sent_emails();
+    assert!(sent
+        .last()
+        .unwrap()
+        .content
+        .contains(&format!("/password-reset?token={}", token)));
+
+    let response = app.get_password_reset_page(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "text/html; charset=utf-8"
+    );
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("assets/password_reset.js"));
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_should_set_new_password() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup_and_login(&app, &email).await;
+    let token = request_reset_token(&app, &email).await;
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "NewPassword456!",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let old_login = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(old_login.status().as_u16(), 401);
+
+    let new_login = app
+        .post_login(&serde_json::json!({ "email": email, "password": "NewPassword456!" }))
+        .await;
+    assert_eq!(new_login.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_should_revoke_existing_refresh_tokens() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    let refresh_token = signup_and_login(&app, &email).await;
+    let token = request_reset_token(&app, &email).await;
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "NewPassword456!",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let refresh_token = RefreshToken::parse(Secret::new(refresh_token)).unwrap();
+    let family = app
+        .refresh_token_store
+        .read()
+        .await
+        .get_family(&refresh_token)
+        .await
+        .unwrap();
+    let result = app
+        .refresh_token_store
+        .read()
+        .await
+        .get_current_token(&family.id)
+        .await;
+    assert_eq!(result, Err(RefreshTokenStoreError::TokenNotFound));
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_should_return_401_if_token_reused() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup_and_login(&app, &email).await;
+    let token = request_reset_token(&app, &email).await;
+
+    let body = serde_json::json!({
+        "token": token,
+        "newPassword": "NewPassword456!",
+    });
+    let response = app.post_password_reset_confirm(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_password_reset_confirm(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(error_response.error, "Invalid token");
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_should_return_401_if_token_unknown() {
+    let mut app = TestApp::new().await;
+
+    for token in ["a".repeat(43), "short".to_owned()] {
+        let response = app
+            .post_password_reset_confirm(&serde_json::json!({
+                "token": token,
+                "newPassword": "NewPassword456!",
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_should_return_400_and_keep_token_if_password_weak() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup_and_login(&app, &email).await;
+    let token = request_reset_token(&app, &email).await;
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "weak",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "NewPassword456!",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,142 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::domain::{ErrorResponse, LoginAttemptId, RateLimit, RateLimits};
+use std::time::Duration;
+use uuid::Uuid;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+fn rate_limits(per_ip: u32, per_email: u32) -> RateLimits {
+    let mut rate_limits = RateLimits::default();
+    for limit in [&mut rate_limits.login, &mut rate_limits.verify_2fa] {
+        limit.per_ip = RateLimit::new(per_ip, WINDOW);
+        limit.per_email = RateLimit::new(per_email, WINDOW);
+    }
+    rate_limits
+}
+
+// Every test shares the Redis counters, so each one makes up its own client addresses
+fn get_random_ip() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2])
+}
+
+async fn post_from(
+    app: &TestApp,
+    path: &str,
+    ip: &str,
+    body: &serde_json::Value,
+) -> reqwest::Response {
+    app.http_client
+        .post(format!("{}{}", &app.address, path))
+        .header("x-real-ip", ip)
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn assert_too_many_requests(response: reqwest::Response) {
+    assert_eq!(response.status().as_u16(), 429);
+    let retry_after: u64 = response
+        .headers()
+        .get("retry-after")
+        .expect("No Retry-After header")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=WINDOW.as_secs()).contains(&retry_after));
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .error,
+        "Too many requests"
+    );
+}
+
+#[tokio::test]
+async fn should_return_429_once_an_email_runs_out_of_login_attempts() {
+    let mut app = TestApp::with_rate_limits(rate_limits(100, 3)).await;
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": "Password123!",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let wrong_password = serde_json::json!({ "email": email, "password": "WrongPassword123!" });
+    for path in ["/login", "/login", "/login/token"] {
+        let response = post_from(&app, path, &get_random_ip(), &wrong_password).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // Even the right password, however the address is spelled
+    let response = post_from(
+        &app,
+        "/login",
+        &get_random_ip(),
+        &serde_json::json!({ "email": email.to_uppercase(), "password": "Password123!" }),
+    )
+    .await;
+    assert_too_many_requests(response).await;
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_429_once_an_address_runs_out_of_login_attempts() {
+    let mut app = TestApp::with_rate_limits(rate_limits(2, 100)).await;
+    let ip = get_random_ip();
+
+    for _ in 0..2 {
+        let body = serde_json::json!({ "email": get_random_email(), "password": "Password123!" });
+        let response = post_from(&app, "/login", &ip, &body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let body = serde_json::json!({ "email": get_random_email(), "password": "Password123!" });
+    let response = post_from(&app, "/login", &ip, &body).await;
+    assert_too_many_requests(response).await;
+
+    // Other clients are unaffected
+    let response = post_from(&app, "/login", &get_random_ip(), &body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_429_once_an_email_runs_out_of_2fa_attempts() {
+    let mut app = TestApp::with_rate_limits(rate_limits(100, 2)).await;
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": LoginAttemptId::default().as_ref(),
+        "2FACode": "123456"
+    });
+
+    for path in ["/verify-2fa", "/verify-2fa/token"] {
+        let response = post_from(&app, path, &get_random_ip(), &body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = post_from(&app, "/verify-2fa", &get_random_ip(), &body).await;
+    assert_too_many_requests(response).await;
+
+    // Password logins count separately
+    let response = post_from(
+        &app,
+        "/login",
+        &get_random_ip(),
+        &serde_json::json!({ "email": email, "password": "Password123!" }),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
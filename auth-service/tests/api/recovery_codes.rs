@@ -0,0 +1,157 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::routes::{
+    RecoveryCodesResponse, RecoveryCodesStatusResponse, SignupResponse, TwoFactorAuthResponse,
+};
+
+/// Signs up a 2FA user and returns their email and initial recovery codes
+async fn signup_with_2fa(app: &TestApp) -> (String, Vec<String>) {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let signup: SignupResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    (email, signup.recovery_codes.expect("No recovery codes"))
+}
+
+/// Runs the login + verify_2fa dance with `code` and returns the verify_2fa status
+async fn login_with_code(app: &TestApp, email: &str, code: &str) -> u16 {
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+    let two_fa: TwoFactorAuthResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+
+    app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": two_fa.login_attempt_id,
+        "2FACode": code,
+    }))
+    .await
+    .status()
+    .as_u16()
+}
+
+async fn remaining(app: &TestApp) -> usize {
+    let response = app.get_recovery_codes_status().await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<RecoveryCodesStatusResponse>()
+        .await
+        .expect("Failed to parse response body")
+        .remaining
+}
+
+#[tokio::test]
+async fn signup_should_return_recovery_codes_only_with_2fa() {
+    let mut app = TestApp::new().await;
+
+    let (_, codes) = signup_with_2fa(&app).await;
+    assert_eq!(codes.len(), 10);
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": get_random_email(),
+            "password": "Password123!",
+            "requires2FA": false
+        }))
+        .await;
+    let signup: SignupResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert!(signup.recovery_codes.is_none());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_2fa_should_accept_recovery_code_once() {
+    let mut app = TestApp::new().await;
+    let (email, codes) = signup_with_2fa(&app).await;
+
+    // Codes are accepted regardless of case and grouping
+    let typed = codes[0].to_uppercase().replace('-', " ");
+    assert_eq!(login_with_code(&app, &email, &typed).await, 200);
+    assert_eq!(remaining(&app).await, 9);
+
+    assert_eq!(login_with_code(&app, &email, &codes[0]).await, 401);
+    assert_eq!(login_with_code(&app, &email, &codes[1]).await, 200);
+    assert_eq!(remaining(&app).await, 8);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_2fa_should_reject_unknown_recovery_code() {
+    let mut app = TestApp::new().await;
+    let (email, _) = signup_with_2fa(&app).await;
+
+    assert_eq!(login_with_code(&app, &email, "abcde-fghjk").await, 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn regenerate_should_replace_all_codes() {
+    let mut app = TestApp::new().await;
+    let (email, old_codes) = signup_with_2fa(&app).await;
+    assert_eq!(login_with_code(&app, &email, &old_codes[0]).await, 200);
+
+    let response = app
+        .post_regenerate_recovery_codes(&serde_json::json!({ "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let new_codes = response
+        .json::<RecoveryCodesResponse>()
+        .await
+        .expect("Failed to parse response body")
+        .recovery_codes;
+    assert_eq!(new_codes.len(), 10);
+    assert_eq!(remaining(&app).await, 10);
+
+    assert_eq!(login_with_code(&app, &email, &old_codes[1]).await, 401);
+    assert_eq!(login_with_code(&app, &email, &new_codes[0]).await, 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn regenerate_should_return_401_if_password_incorrect() {
+    let mut app = TestApp::new().await;
+    let (email, codes) = signup_with_2fa(&app).await;
+    assert_eq!(login_with_code(&app, &email, &codes[0]).await, 200);
+
+    let response = app
+        .post_regenerate_recovery_codes(&serde_json::json!({ "password": "WrongPassword123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(remaining(&app).await, 9);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_recovery_codes_status().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_regenerate_recovery_codes(&serde_json::json!({ "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,261 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{
+        BannedTokenStore, ErrorResponse, RefreshToken, RefreshTokenStore, RefreshTokenStoreError,
+        UserStore,
+    },
+    routes::TokenResponse,
+    utils::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
+use chrono::Utc;
+use reqwest::Url;
+use secrecy::Secret;
+
+async fn signup_and_login(app: &TestApp) -> String {
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "Password123!",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let refresh_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+        .expect("No refresh cookie found")
+        .value()
+        .to_owned();
+    refresh_token
+}
+
+fn set_refresh_cookie(app: &TestApp, value: &str) {
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, value
+        ),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+}
+
+#[tokio::test]
+async fn should_return_400_if_refresh_cookie_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(error_response.error, "Missing token");
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_refresh_token() {
+    let mut app = TestApp::new().await;
+
+    for value in ["invalid", "0123456789012345678901234567890123456789012"] {
+        set_refresh_cookie(&app, value);
+
+        let response = app.post_refresh_token().await;
+        assert_eq!(response.status().as_u16(), 401, "Failed for: {}", value);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_200_and_rotate_refresh_token() {
+    let mut app = TestApp::new().await;
+    let original = signup_and_login(&app).await;
+
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    assert!(!auth_cookie.value().is_empty());
+
+    let rotated = response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+        .expect("No refresh cookie found")
+        .value()
+        .to_owned();
+    assert_ne!(rotated, original, "Refresh token should be rotated");
+
+    // The rotated token can be used for the next refresh
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_revoke_family_if_refresh_token_reused() {
+    let mut app = TestApp::new().await;
+    let original = signup_and_login(&app).await;
+
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let rotated = response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+        .expect("No refresh cookie found")
+        .value()
+        .to_owned();
+
+    // Replaying the spent token is rejected...
+    set_refresh_cookie(&app, &original);
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // ...and takes the rest of the family down with it
+    set_refresh_cookie(&app, &rotated);
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_after_logout() {
+    let mut app = TestApp::new().await;
+    let original = signup_and_login(&app).await;
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let refresh_token = RefreshToken::parse(Secret::new(original.clone())).unwrap();
+    let family = app
+        .refresh_token_store
+        .read()
+        .await
+        .get_family(&refresh_token)
+        .await
+        .expect("Refresh token record should outlive its family");
+    let current = app
+        .refresh_token_store
+        .read()
+        .await
+        .get_current_token(&family.id)
+        .await;
+    assert_eq!(
+        current.err(),
+        Some(RefreshTokenStoreError::TokenNotFound),
+        "Refresh token family should be revoked after logout"
+    );
+
+    set_refresh_cookie(&app, &original);
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+async fn signup_and_login_token(app: &TestApp, email: &str) -> TokenResponse {
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({ "email": email, "password": "Password123!" });
+    let response = app.post_login_token(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+}
+
+#[tokio::test]
+async fn should_let_only_one_concurrent_refresh_succeed() {
+    let mut app = TestApp::new().await;
+    let tokens = signup_and_login_token(&app, &get_random_email()).await;
+
+    let body = serde_json::json!({ "refreshToken": tokens.refresh_token });
+    let (first, second) = tokio::join!(
+        app.post_refresh_token_body(&body),
+        app.post_refresh_token_body(&body)
+    );
+    let mut statuses = [first.status().as_u16(), second.status().as_u16()];
+    statuses.sort();
+    assert_eq!(statuses, [200, 401]);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_403_if_account_disabled() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    let tokens = signup_and_login_token(&app, &email).await;
+
+    // Disabled directly in the store, so the family survives and only the refresh check stands
+    let user_id = app.get_user_id(&email).await;
+    app.user_store
+        .write()
+        .await
+        .set_disabled(&user_id, true)
+        .await
+        .unwrap();
+
+    let body = serde_json::json!({ "refreshToken": tokens.refresh_token });
+    let response = app.post_refresh_token_body(&body).await;
+    assert_eq!(response.status().as_u16(), 403);
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(error_response.error, "Account disabled");
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_session_revoked() {
+    let mut app = TestApp::new().await;
+    let tokens = signup_and_login_token(&app, &get_random_email()).await;
+
+    // Ban the session id without touching the family, as a ban that outlived a failed revoke would
+    let refresh_token = RefreshToken::parse(Secret::new(tokens.refresh_token.clone())).unwrap();
+    let family = app
+        .refresh_token_store
+        .read()
+        .await
+        .get_family(&refresh_token)
+        .await
+        .unwrap();
+    let expires_at = Utc::now().timestamp() as usize + 600;
+    app.banned_token_store
+        .write()
+        .await
+        .add_banned_token(&family.id, expires_at)
+        .await
+        .unwrap();
+
+    let body = serde_json::json!({ "refreshToken": tokens.refresh_token });
+    let response = app.post_refresh_token_body(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
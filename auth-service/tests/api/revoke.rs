@@ -0,0 +1,135 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::utils::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME};
+use reqwest::Url;
+
+/// Signs up and logs in, returning the JWT and refresh token of the session
+async fn signup_and_login(app: &TestApp) -> (String, String) {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let cookie = |name: &str| {
+        response
+            .cookies()
+            .find(|cookie| cookie.name() == name)
+            .expect("Cookie not found")
+            .value()
+            .to_owned()
+    };
+    (cookie(JWT_COOKIE_NAME), cookie(REFRESH_TOKEN_COOKIE_NAME))
+}
+
+async fn verify_token_status(app: &TestApp, token: &str) -> u16 {
+    app.post_verify_token(&serde_json::json!({ "token": token }))
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_revoke_access_token() {
+    let mut app = TestApp::new().await;
+    let (jwt, _) = signup_and_login(&app).await;
+    assert_eq!(verify_token_status(&app, &jwt).await, 200);
+
+    let response = app
+        .post_revoke(&[("token", jwt.as_str()), ("token_type_hint", "access_token")])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(verify_token_status(&app, &jwt).await, 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_revoke_refresh_token_and_its_session() {
+    let mut app = TestApp::new().await;
+    let (jwt, refresh_token) = signup_and_login(&app).await;
+
+    let response = app
+        .post_revoke(&[
+            ("token", refresh_token.as_str()),
+            ("token_type_hint", "refresh_token"),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, refresh_token
+        ),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Access tokens issued to the session go with it
+    assert_eq!(verify_token_status(&app, &jwt).await, 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_revoke_despite_wrong_or_missing_hint() {
+    let mut app = TestApp::new().await;
+    let (jwt, refresh_token) = signup_and_login(&app).await;
+
+    let response = app
+        .post_revoke(&[
+            ("token", jwt.as_str()),
+            ("token_type_hint", "refresh_token"),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(verify_token_status(&app, &jwt).await, 401);
+
+    let response = app.post_revoke(&[("token", refresh_token.as_str())]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, refresh_token
+        ),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_200_for_unknown_tokens() {
+    let mut app = TestApp::new().await;
+
+    for token in ["not-a-token", "", "a.b.c"] {
+        let response = app.post_revoke(&[("token", token)]).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_422_if_token_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .post_revoke(&[("token_type_hint", "access_token")])
+        .await;
+    assert_eq!(response.status().as_u16(), 422);
+
+    app.clean_up().await.unwrap();
+}
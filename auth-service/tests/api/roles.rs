@@ -0,0 +1,150 @@
+use crate::helpers::{get_random_email, TestApp, API_CLIENT_ID, API_CLIENT_SECRET};
+use auth_service::{
+    domain::{ErrorResponse, Role, UserId, UserStore, UserStoreError, ADMIN_ROLE},
+    routes::{IntrospectResponse, RolesResponse},
+    utils::{decode_claims, JWT_COOKIE_NAME},
+};
+
+/// Signs up and logs in, returning the user's id
+async fn signup_and_login(app: &TestApp) -> UserId {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "Password123!" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    app.get_user_id(&email).await
+}
+
+/// Gives the user the admin role and refreshes their token so it carries the role
+async fn make_admin(app: &TestApp, user_id: &UserId) -> String {
+    let admin = Role::parse(ADMIN_ROLE).unwrap();
+    let assigned = app
+        .user_store
+        .write()
+        .await
+        .assign_role(user_id, &admin)
+        .await
+        .expect("Failed to assign role");
+    assert!(assigned);
+
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let jwt = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    jwt
+}
+
+#[tokio::test]
+async fn should_return_403_without_required_role() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    let response = app.get_admin_roles().await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .error,
+        "Forbidden"
+    );
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_admin_roles().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_embed_roles_in_token_after_refresh() {
+    let mut app = TestApp::new().await;
+    let user_id = signup_and_login(&app).await;
+
+    let jwt = make_admin(&app, &user_id).await;
+    let claims = decode_claims(&jwt).expect("Failed to decode JWT");
+    assert_eq!(claims.roles, vec![ADMIN_ROLE]);
+    assert_eq!(claims.scope, "users:read users:write");
+
+    let response = app.get_admin_roles().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let roles = response
+        .json::<RolesResponse>()
+        .await
+        .expect("Could not deserialize response body to RolesResponse")
+        .roles;
+    let admin = roles
+        .iter()
+        .find(|role| role.name == ADMIN_ROLE)
+        .expect("admin role should be defined");
+    assert_eq!(admin.permissions, vec!["users:read", "users:write"]);
+
+    // Resource servers see the permissions as the token's scope
+    let response = app
+        .post_introspect(
+            &[("token", jwt.as_str())],
+            Some((API_CLIENT_ID, API_CLIENT_SECRET)),
+        )
+        .await;
+    let body = response
+        .json::<IntrospectResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectResponse");
+    assert_eq!(body.scope.as_deref(), Some("users:read users:write"));
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_assign_and_remove_roles() {
+    let mut app = TestApp::new().await;
+    let user_id = signup_and_login(&app).await;
+    let admin = Role::parse(ADMIN_ROLE).unwrap();
+
+    {
+        let mut store = app.user_store.write().await;
+        assert!(store.get_roles(&user_id).await.unwrap().roles.is_empty());
+        assert!(store.assign_role(&user_id, &admin).await.unwrap());
+        assert!(!store.assign_role(&user_id, &admin).await.unwrap());
+        assert_eq!(
+            store.get_roles(&user_id).await.unwrap().roles,
+            vec![ADMIN_ROLE]
+        );
+
+        let unknown = Role::parse("unknown").unwrap();
+        assert_eq!(
+            store.assign_role(&user_id, &unknown).await,
+            Err(UserStoreError::RoleNotFound)
+        );
+        let stranger = UserId::default();
+        assert_eq!(
+            store.assign_role(&stranger, &admin).await,
+            Err(UserStoreError::UserNotFound)
+        );
+
+        assert!(store.remove_role(&user_id, &admin).await.unwrap());
+        assert!(!store.remove_role(&user_id, &admin).await.unwrap());
+        assert_eq!(store.get_roles(&user_id).await.unwrap(), Default::default());
+    }
+
+    app.clean_up().await.unwrap();
+}
@@ -0,0 +1,199 @@
+use crate::helpers::{get_random_email, loopback, TestApp};
+use auth_service::{
+    domain::ErrorResponse,
+    routes::{SessionResponse, SessionsResponse},
+    utils::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+};
+use reqwest::Url;
+
+async fn signup(app: &TestApp) -> String {
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    random_email
+}
+
+/// Log in and return the JWT and refresh token cookies the session was given
+async fn login(app: &TestApp, email: &str) -> (String, String) {
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let cookie = |name: &str| {
+        response
+            .cookies()
+            .find(|cookie| cookie.name() == name)
+            .expect("Cookie not found")
+            .value()
+            .to_owned()
+    };
+    (cookie(JWT_COOKIE_NAME), cookie(REFRESH_TOKEN_COOKIE_NAME))
+}
+
+fn set_cookie(app: &TestApp, name: &str, value: &str) {
+    app.cookie_jar.add_cookie_str(
+        &format!("{}={}; HttpOnly; SameSite=Lax; Path=/", name, value),
+        &app.address.parse::<Url>().expect("Failed to parse URL"),
+    );
+}
+
+async fn get_sessions(app: &TestApp) -> Vec<SessionResponse> {
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.post_revoke_session("some-session").await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_list_current_session_with_device() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    login(&app, &email).await;
+
+    let sessions = get_sessions(&app).await;
+    assert_eq!(sessions.len(), 1);
+    let session = &sessions[0];
+    assert!(session.current);
+    assert!(!session.device.is_empty());
+    assert!(session.ip.is_some());
+    assert!(session.created_at <= session.last_seen_at);
+
+    app.clean_up().await.unwrap();
+}
+
+/// Log in claiming to come from `ip`, as nginx would tell it
+async fn login_from(app: &TestApp, email: &str, ip: &str) {
+    let response = app
+        .http_client
+        .post(format!("{}/login", &app.address))
+        .header("x-real-ip", ip)
+        .json(&serde_json::json!({
+            "email": email,
+            "password": "Password123!",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_record_ip_told_by_trusted_proxy() {
+    let mut app = TestApp::with_trusted_proxies(loopback()).await;
+    let email = signup(&app).await;
+    login_from(&app, &email, "203.0.113.7").await;
+
+    let sessions = get_sessions(&app).await;
+    assert_eq!(sessions[0].ip.as_deref(), Some("203.0.113.7"));
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_ignore_ip_told_by_untrusted_peer() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    login_from(&app, &email, "203.0.113.7").await;
+
+    let sessions = get_sessions(&app).await;
+    assert_eq!(sessions[0].ip.as_deref(), Some("127.0.0.1"));
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_list_one_session_per_login() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    login(&app, &email).await;
+    login(&app, &email).await;
+
+    let sessions = get_sessions(&app).await;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions.iter().filter(|session| session.current).count(), 1);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_revoke_other_session() {
+    let mut app = TestApp::new().await;
+    let email = signup(&app).await;
+    let (old_jwt, old_refresh_token) = login(&app, &email).await;
+    login(&app, &email).await;
+
+    let other = get_sessions(&app)
+        .await
+        .into_iter()
+        .find(|session| !session.current)
+        .expect("Other session not listed");
+
+    let response = app.post_revoke_session(&other.id).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let sessions = get_sessions(&app).await;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+
+    // Both the JWT and the refresh token of the revoked session stop working
+    set_cookie(&app, JWT_COOKIE_NAME, &old_jwt);
+    let response = app.get_sessions().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    set_cookie(&app, REFRESH_TOKEN_COOKIE_NAME, &old_refresh_token);
+    let response = app.post_refresh_token().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_404_for_unknown_or_foreign_session() {
+    let mut app = TestApp::new().await;
+    let victim = signup(&app).await;
+    login(&app, &victim).await;
+    let victim_session = get_sessions(&app).await.remove(0);
+
+    let attacker = signup(&app).await;
+    login(&app, &attacker).await;
+
+    let response = app.post_revoke_session("unknown-session").await;
+    assert_eq!(response.status().as_u16(), 404);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .error,
+        "Session not found"
+    );
+
+    let response = app.post_revoke_session(&victim_session.id).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    app.clean_up().await.unwrap();
+}
@@ -111,6 +111,32 @@ async fn should_return_409_if_email_already_exists() {
     app.clean_up().await.unwrap();
 }
 
+#[tokio::test]
+async fn should_return_409_if_email_differs_only_in_case() {
+    let mut app = TestApp::new().await;
+    let random_email = get_random_email();
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": random_email,
+            "password": "Password123!",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": format!(" {} ", random_email.to_uppercase()),
+            "password": "Password123!",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 409);
+
+    app.clean_up().await.unwrap();
+}
+
 #[tokio::test]
 async fn should_return_201_if_valid_input() {
     let mut app = TestApp::new().await;
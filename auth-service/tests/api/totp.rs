@@ -0,0 +1,205 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::{
+    domain::{ErrorResponse, TotpSecret, TwoFAMethod},
+    routes::{TotpEnrollResponse, TwoFactorAuthResponse},
+    utils::JWT_COOKIE_NAME,
+};
+use chrono::Utc;
+use data_encoding::BASE32_NOPAD;
+
+async fn signup_and_login(app: &TestApp) -> String {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app.post_login(&login_body(&email)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    email
+}
+
+fn login_body(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    })
+}
+
+async fn enroll(app: &TestApp) -> TotpSecret {
+    let response = app.post_totp_enroll().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let enrollment: TotpEnrollResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert!(enrollment
+        .otpauth_uri
+        .contains(&format!("secret={}", enrollment.secret)));
+
+    TotpSecret::from_bytes(BASE32_NOPAD.decode(enrollment.secret.as_bytes()).unwrap()).unwrap()
+}
+
+fn now() -> u64 {
+    Utc::now().timestamp().unsigned_abs()
+}
+
+#[tokio::test]
+async fn enroll_should_return_400_if_jwt_cookie_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app.post_totp_enroll().await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": "123456" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_should_return_400_if_not_enrolled() {
+    let mut app = TestApp::new().await;
+    signup_and_login(&app).await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": "123456" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn confirm_should_return_401_if_code_incorrect() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+    let secret = enroll(&app).await;
+
+    // A code from ten minutes ago is out of the accepted window
+    let stale = secret.generate(now() - 600);
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": stale.as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Still not enabled: login keeps skipping 2FA
+    let response = app.post_login(&login_body(&email)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_login_with_totp_once_confirmed() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+    let secret = enroll(&app).await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": secret.generate(now()).as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let emails_before_login = app.email_client.sent_emails().len();
+    let response = app.post_login(&login_body(&email)).await;
+    assert_eq!(response.status().as_u16(), 206);
+    let two_fa: TwoFactorAuthResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(two_fa.two_fa_method, TwoFAMethod::Totp);
+    assert_eq!(
+        app.email_client.sent_emails().len(),
+        emails_before_login,
+        "No code should be emailed to TOTP users"
+    );
+
+    // The confirmation code's step is used up, so take the next one (within the allowed skew)
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": two_fa.login_attempt_id,
+            "2FACode": secret.generate(now() + 30).as_ref(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME));
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_2fa_should_reject_replayed_totp_code() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+    let secret = enroll(&app).await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": secret.generate(now()).as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let code = secret.generate(now() + 30);
+    for expected_status in [200, 401] {
+        let response = app.post_login(&login_body(&email)).await;
+        assert_eq!(response.status().as_u16(), 206);
+        let two_fa: TwoFactorAuthResponse = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        let response = app
+            .post_verify_2fa(&serde_json::json!({
+                "email": email,
+                "loginAttemptId": two_fa.login_attempt_id,
+                "2FACode": code.as_ref(),
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), expected_status);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_2fa_should_return_401_if_totp_code_incorrect() {
+    let mut app = TestApp::new().await;
+    let email = signup_and_login(&app).await;
+    let secret = enroll(&app).await;
+
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": secret.generate(now()).as_ref() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&login_body(&email)).await;
+    let two_fa: TwoFactorAuthResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": two_fa.login_attempt_id,
+            "2FACode": secret.generate(now() - 600).as_ref(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(error_response.error, "Incorrect credentials");
+
+    app.clean_up().await.unwrap();
+}
@@ -1,9 +1,19 @@
 use crate::helpers::{get_random_email, TestApp};
 use auth_service::{
     domain::{LoginAttemptId, TwoFACode, TwoFACodeStore},
-    utils::JWT_COOKIE_NAME,
+    utils::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
 };
-use secrecy::{ExposeSecret, Secret};
+
+// verify_2fa looks up the user's 2FA method, so the account has to exist
+async fn signup_with_2fa(app: &TestApp, email: &str) {
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+}
 
 #[tokio::test]
 async fn should_return_422_if_malformed_input() {
@@ -88,24 +98,20 @@ async fn should_return_401_if_user_does_not_exist() {
 async fn should_return_200_if_correct_code() {
     let mut app = TestApp::new().await;
     let email = get_random_email();
-    let parsed_email =
-        auth_service::domain::Email::parse(Secret::new(email.clone())).expect("Invalid email");
+    signup_with_2fa(&app, &email).await;
+    let user_id = app.get_user_id(&email).await;
     let login_attempt_id = LoginAttemptId::default();
     let two_fa_code = TwoFACode::default();
 
     app.two_fa_code_store
         .write()
         .await
-        .add_code(
-            parsed_email.clone(),
-            login_attempt_id.clone(),
-            two_fa_code.clone(),
-        )
+        .add_code(user_id, login_attempt_id.clone(), two_fa_code.clone())
         .await
         .expect("Failed to store two_fa_code");
 
     let body = serde_json::json!({
-    "email": parsed_email.as_ref().expose_secret(),
+    "email": email,
         "loginAttemptId": login_attempt_id.as_ref(),
         "2FACode": two_fa_code.as_ref()
     });
@@ -123,6 +129,14 @@ async fn should_return_200_if_correct_code() {
         "Expected {} cookie in response",
         JWT_COOKIE_NAME
     );
+    assert!(
+        cookies.iter().any(|c| c
+            .to_str()
+            .unwrap()
+            .contains(&format!("{}=", REFRESH_TOKEN_COOKIE_NAME))),
+        "Expected {} cookie in response",
+        REFRESH_TOKEN_COOKIE_NAME
+    );
 
     app.clean_up().await.unwrap();
 }
@@ -131,24 +145,20 @@ async fn should_return_200_if_correct_code() {
 async fn should_return_401_if_same_code_twice() {
     let mut app = TestApp::new().await;
     let email = get_random_email();
-    let parsed_email =
-        auth_service::domain::Email::parse(Secret::new(email.clone())).expect("Invalid email");
+    signup_with_2fa(&app, &email).await;
+    let user_id = app.get_user_id(&email).await;
     let login_attempt_id = LoginAttemptId::default();
     let two_fa_code = TwoFACode::default();
 
     app.two_fa_code_store
         .write()
         .await
-        .add_code(
-            parsed_email.clone(),
-            login_attempt_id.clone(),
-            two_fa_code.clone(),
-        )
+        .add_code(user_id, login_attempt_id.clone(), two_fa_code.clone())
         .await
         .expect("Failed to store two_fa_code");
 
     let body = serde_json::json!({
-    "email": parsed_email.as_ref().expose_secret(),
+    "email": email,
         "loginAttemptId": login_attempt_id.as_ref(),
         "2FACode": two_fa_code.as_ref()
     });
@@ -0,0 +1,205 @@
+use crate::helpers::{get_random_email, TestApp};
+use auth_service::domain::{ErrorResponse, UnverifiedLoginPolicy};
+
+async fn signup(app: &TestApp, email: &str) {
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+fn login_body(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "email": email,
+        "password": "Password123!",
+    })
+}
+
+#[tokio::test]
+async fn signup_should_send_verification_email() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+
+    let sent = app.email_client.sent_emails();
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].content.contains("/verify-email?token="));
+    assert!(app.get_emailed_token(&email).is_some());
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_200_and_allow_login_once_verified() {
+    let mut app = TestApp::with_unverified_login_policy(UnverifiedLoginPolicy::Deny).await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+
+    let response = app.post_login(&login_body(&email)).await;
+    assert_eq!(response.status().as_u16(), 403);
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+    assert_eq!(error_response.error, "Email not verified");
+
+    let token = app.get_emailed_token(&email).unwrap();
+    let response = app.get_verify_email(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&login_body(&email)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_token_reused() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+
+    let token = app.get_emailed_token(&email).unwrap();
+    let response = app.get_verify_email(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_verify_email(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let mut app = TestApp::new().await;
+
+    for token in ["a".repeat(43), "short".to_owned()] {
+        let response = app.get_verify_email(&token).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_401_if_token_signature_stripped() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+
+    let signed = app.get_emailed_token(&email).unwrap();
+    let (bare, _) = signed.split_once('.').unwrap();
+    let response = app.get_verify_email(bare).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.get_verify_email(&signed).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn should_return_400_if_token_missing() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .http_client
+        .get(format!("{}/verify-email", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn deny_policy_should_not_reveal_unverified_status_to_wrong_password() {
+    let mut app = TestApp::with_unverified_login_policy(UnverifiedLoginPolicy::Deny).await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": email,
+            "password": "WrongPassword123!",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn password_reset_should_mark_email_verified() {
+    let mut app = TestApp::with_unverified_login_policy(UnverifiedLoginPolicy::Deny).await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = app.get_emailed_token(&email).unwrap();
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "Password123!",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&login_body(&email)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn signup_should_succeed_and_resend_should_recover_if_email_fails() {
+    let mut app = TestApp::with_unverified_login_policy(UnverifiedLoginPolicy::Deny).await;
+    let email = get_random_email();
+
+    app.email_client.set_failing(true);
+    signup(&app, &email).await;
+    assert!(app.get_emailed_token(&email).is_none());
+    app.email_client.set_failing(false);
+
+    let response = app
+        .post_resend_verification(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = app
+        .get_emailed_token(&email)
+        .expect("No verification email was sent");
+
+    let response = app.get_verify_email(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post_login(&login_body(&email)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn resend_should_not_email_unknown_or_verified_accounts() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    signup(&app, &email).await;
+    let token = app.get_emailed_token(&email).unwrap();
+    let response = app.get_verify_email(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    for email in [email, get_random_email(), "not-an-email".to_owned()] {
+        let response = app
+            .post_resend_verification(&serde_json::json!({ "email": email }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert_eq!(app.email_client.sent_emails().len(), 1);
+
+    app.clean_up().await.unwrap();
+}